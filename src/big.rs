@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{Signed, ToPrimitive, Zero};
+
+use crate::{derive::derive, eval::EvalErr, Expr, Op, Rational};
+
+// results any larger than this are treated as overflowing rather than exhausting memory
+const MAX_BITS: u64 = 1 << 16;
+
+impl TryFrom<Rational> for BigRational {
+    type Error = EvalErr;
+
+    fn try_from(rational: Rational) -> Result<Self, Self::Error> {
+        if rational.denominator == 0 {
+            return Err(EvalErr::DivisionByZero);
+        }
+        Ok(BigRational::new(
+            BigInt::from(rational.numerator),
+            BigInt::from(rational.denominator),
+        ))
+    }
+}
+
+impl TryFrom<BigRational> for Rational {
+    type Error = EvalErr;
+
+    fn try_from(rational: BigRational) -> Result<Self, Self::Error> {
+        Ok(Rational::new(
+            rational.numer().to_i64().ok_or(EvalErr::Overflow)?,
+            rational.denom().to_u64().ok_or(EvalErr::Overflow)?,
+        ))
+    }
+}
+
+fn pow(base: BigRational, exponent: BigRational) -> Result<BigRational, EvalErr> {
+    let numerator = exponent.numer().to_i32().ok_or(EvalErr::Overflow)?;
+    let index = exponent.denom().to_u32().ok_or(EvalErr::Overflow)?;
+    if numerator < 0 && base.is_zero() {
+        return Err(EvalErr::DivisionByZero);
+    }
+    let bits = base.numer().bits().max(base.denom().bits());
+    if bits.saturating_mul(numerator.unsigned_abs() as u64) > MAX_BITS {
+        return Err(EvalErr::Overflow);
+    }
+
+    let power = base.pow(numerator);
+    if index == 1 {
+        return Ok(power);
+    }
+    if power.is_negative() && index % 2 == 0 {
+        return Err(EvalErr::NonReal);
+    }
+
+    let root = BigRational::new(power.numer().nth_root(index), power.denom().nth_root(index));
+    if root.pow(index as i32) == power {
+        Ok(root)
+    } else {
+        Err(EvalErr::Irrational)
+    }
+}
+
+/// Evaluates with arbitrary precision, so it only fails on expressions that `eval` can't
+/// represent at all.
+pub fn eval_big(expr: &Expr, vars: &HashMap<char, Rational>) -> Result<BigRational, EvalErr> {
+    let answer = match expr {
+        Expr::Rational(rational) => BigRational::try_from(*rational)?,
+        Expr::Pair(pair) => {
+            let lval = eval_big(&pair.left, vars)?;
+            let rval = eval_big(&pair.right, vars)?;
+
+            match pair.op {
+                Op::Add => lval + rval,
+                Op::Sub => lval - rval,
+                Op::Mul => lval * rval,
+                Op::Div if rval.is_zero() => return Err(EvalErr::DivisionByZero),
+                Op::Div => lval / rval,
+                Op::Pow => pow(lval, rval)?,
+            }
+        }
+        Expr::Negative(expr) => -eval_big(expr, vars)?,
+        Expr::Variable(unknown) => match vars.get(unknown) {
+            Some(value) => BigRational::try_from(*value)?,
+            None => return Err(EvalErr::EncounteredUnknown(*unknown)),
+        },
+        Expr::Derivative(expr, var) => eval_big(&derive(expr, *var), vars)?,
+        Expr::Func(func, _) => return Err(EvalErr::NonEvaluableFunc(*func)),
+        Expr::Constant(_) => return Err(EvalErr::Irrational),
+    };
+
+    Ok(answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval::eval_exact_with, ExactVal, Pair};
+
+    fn pair(left: Expr, op: Op, right: Expr) -> Expr {
+        Pair::new(left, op, right).into()
+    }
+
+    fn int(n: i64) -> Expr {
+        Rational::int(n).into()
+    }
+
+    #[test]
+    fn intermediate_overflow_falls_back_to_big_rationals() {
+        let expr = pair(pair(int(i64::MAX), Op::Mul, int(2)), Op::Div, int(4));
+        assert_eq!(
+            eval_exact_with(&expr, &HashMap::new()).ok(),
+            Some(ExactVal::Rational(Rational::new(i64::MAX, 2)))
+        );
+
+        let expr = pair(
+            pair(Expr::Variable('x'), Op::Pow, int(3)),
+            Op::Div,
+            pair(Expr::Variable('x'), Op::Pow, int(2)),
+        );
+        let vars = HashMap::from([('x', Rational::int(i64::MAX))]);
+        assert_eq!(
+            eval_exact_with(&expr, &vars).ok(),
+            Some(ExactVal::Rational(Rational::int(i64::MAX)))
+        );
+    }
+
+    #[test]
+    fn answers_which_still_do_not_fit_overflow() {
+        let expr = pair(int(i64::MAX), Op::Mul, int(i64::MAX));
+        assert!(matches!(
+            eval_exact_with(&expr, &HashMap::new()),
+            Err(EvalErr::Overflow)
+        ));
+
+        let expr = pair(int(2), Op::Pow, int(1 << 20));
+        assert!(matches!(
+            eval_big(&expr, &HashMap::new()),
+            Err(EvalErr::Overflow)
+        ));
+    }
+
+    #[test]
+    fn zero_denominators_divide_by_zero() {
+        let undefined = Rational {
+            numerator: 1,
+            denominator: 0,
+        };
+        assert!(matches!(
+            BigRational::try_from(undefined),
+            Err(EvalErr::DivisionByZero)
+        ));
+
+        let vars = HashMap::from([('x', undefined)]);
+        let expr = pair(Expr::Variable('x'), Op::Add, int(1));
+        assert!(matches!(
+            eval_big(&expr, &vars),
+            Err(EvalErr::DivisionByZero)
+        ));
+        assert!(matches!(
+            eval_big(&undefined.into(), &HashMap::new()),
+            Err(EvalErr::DivisionByZero)
+        ));
+        let expr = pair(int(1), Op::Div, pair(int(i64::MAX), Op::Sub, int(i64::MAX)));
+        assert!(matches!(
+            eval_big(&expr, &HashMap::new()),
+            Err(EvalErr::DivisionByZero)
+        ));
+    }
+}
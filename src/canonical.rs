@@ -0,0 +1,193 @@
+use crate::{Expr, Op, Pair};
+
+/// An expression in canonical form, for use as a `HashMap` or `HashSet` key. `Expr`'s own `Hash` is
+/// structural, so `x + 2` and `2 + x` hash differently, but their keys are equal.
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub struct CanonicalKey(Expr);
+
+impl CanonicalKey {
+    pub fn new(expr: &Expr) -> CanonicalKey {
+        CanonicalKey(canonical(expr))
+    }
+}
+
+// the rational's sign is moved onto the term or factor, so that `-2` and `-(2)` are the same
+fn magnitude(expr: Expr, negative: &mut bool) -> Expr {
+    match expr {
+        Expr::Rational(rational) if rational.numerator < 0 => match rational.checked_neg() {
+            Ok(magnitude) => {
+                *negative = !*negative;
+                Expr::Rational(magnitude)
+            }
+            Err(_) => Expr::Rational(rational),
+        },
+        Expr::Negative(inner) => {
+            *negative = !*negative;
+            *inner
+        }
+        expr => expr,
+    }
+}
+
+fn negate(expr: Expr) -> Expr {
+    match expr {
+        Expr::Rational(rational) => match rational.checked_neg() {
+            Ok(negated) => Expr::Rational(negated),
+            Err(_) => Expr::Negative(Box::new(Expr::Rational(rational))),
+        },
+        expr => Expr::Negative(Box::new(expr)),
+    }
+}
+
+// the terms of a chain of additions and subtractions, along with whether each is subtracted
+fn terms(expr: &Expr, negative: bool, split: &mut Vec<(Expr, bool)>) {
+    match expr {
+        Expr::Pair(pair) if pair.op == Op::Add || pair.op == Op::Sub => {
+            terms(&pair.left, negative, split);
+            terms(&pair.right, negative ^ (pair.op == Op::Sub), split);
+        }
+        Expr::Negative(inner) => terms(inner, !negative, split),
+        expr => {
+            let mut negative = negative;
+            let term = magnitude(canonical(expr), &mut negative);
+            split.push((term, negative));
+        }
+    }
+}
+
+// the factors of a chain of multiplications, with their signs collected into `negative`
+fn factors(expr: &Expr, negative: &mut bool, split: &mut Vec<Expr>) {
+    match expr {
+        Expr::Pair(pair) if pair.op == Op::Mul => {
+            factors(&pair.left, negative, split);
+            factors(&pair.right, negative, split);
+        }
+        Expr::Negative(inner) => {
+            *negative = !*negative;
+            factors(inner, negative, split);
+        }
+        expr => split.push(magnitude(canonical(expr), negative)),
+    }
+}
+
+/// Rewrites `expr` so that expressions which only differ by the order of the operands of `+` and
+/// `*`, how those operations are bracketed, or where their minus signs are written, become
+/// identical. Nothing is evaluated, so `2 * 3` and `6` stay different.
+pub fn canonical(expr: &Expr) -> Expr {
+    match expr {
+        Expr::Pair(pair) if pair.op == Op::Add || pair.op == Op::Sub => sum(expr),
+        Expr::Negative(_) => sum(expr),
+        Expr::Pair(pair) if pair.op == Op::Mul => {
+            let mut negative = false;
+            let mut split = vec![];
+            factors(expr, &mut negative, &mut split);
+            // coefficients go first, as they would be written
+            split.sort_by(|l, r| {
+                let is_rational = |expr: &Expr| matches!(expr, Expr::Rational(_));
+                is_rational(r).cmp(&is_rational(l)).then_with(|| l.cmp(r))
+            });
+
+            let product = split
+                .into_iter()
+                .reduce(|product, factor| Pair::new(product, Op::Mul, factor).into())
+                .expect("Product should have at least two factors");
+            if negative {
+                negate(product)
+            } else {
+                product
+            }
+        }
+        Expr::Pair(pair) => {
+            Pair::new(canonical(&pair.left), pair.op, canonical(&pair.right)).into()
+        }
+        Expr::Func(func, inner) => Expr::Func(*func, Box::new(canonical(inner))),
+        Expr::Derivative(inner, var) => Expr::Derivative(Box::new(canonical(inner)), *var),
+        Expr::Rational(_) | Expr::Variable(_) | Expr::Constant(_) => expr.to_owned(),
+    }
+}
+
+fn sum(expr: &Expr) -> Expr {
+    let mut split = vec![];
+    terms(expr, false, &mut split);
+    split.sort();
+
+    let mut split = split.into_iter();
+    let (first, negative) = split.next().expect("Sum should have at least one term");
+    let first = if negative { negate(first) } else { first };
+    split.fold(first, |sum, (term, negative)| {
+        let op = if negative { Op::Sub } else { Op::Add };
+        Pair::new(sum, op, term).into()
+    })
+}
+
+/// Whether `a` and `b` are the same once both are in canonical form.
+pub fn same(a: &Expr, b: &Expr) -> bool {
+    canonical(a) == canonical(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        collections::hash_map::DefaultHasher,
+        hash::{Hash, Hasher},
+    };
+
+    use super::*;
+    use crate::parse::parse_expr;
+
+    fn parse(input: &str) -> Expr {
+        parse_expr(input).unwrap()
+    }
+
+    fn hash(key: &CanonicalKey) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn assert_same(a: &str, b: &str) {
+        let (a, b) = (parse(a), parse(b));
+        assert!(same(&a, &b), "{a} and {b} should be the same");
+        assert_eq!(CanonicalKey::new(&a), CanonicalKey::new(&b));
+        assert_eq!(hash(&CanonicalKey::new(&a)), hash(&CanonicalKey::new(&b)));
+    }
+
+    #[test]
+    fn operands_of_sums_and_products_are_sorted() {
+        assert_same("2 + x", "x + 2");
+        assert_same("x*y*3", "3*y*x");
+        assert_same("sin(x + 1)", "sin(1 + x)");
+        assert_ne!(parse("2 + x"), parse("x + 2"));
+    }
+
+    #[test]
+    fn chains_are_flattened() {
+        assert_same("(a + b) + c", "a + (b + c)");
+        assert_same("(a*b)*c", "a*(b*c)");
+        assert_same("a - (b - c)", "(a + c) - b");
+    }
+
+    #[test]
+    fn signs_are_normalised() {
+        assert_same("-2*x", "-(2*x)");
+        assert_same("x*(-2)", "-(2*x)");
+        assert_same("x - y", "-y + x");
+        assert_same("-(-x)", "x");
+        assert_same("-3", "-(3)");
+    }
+
+    #[test]
+    fn different_expressions_stay_different() {
+        let different = [
+            ("2*3", "6"),
+            ("x - y", "y - x"),
+            ("x/y", "y/x"),
+            ("x^2", "2^x"),
+        ];
+        for (a, b) in different {
+            let (a, b) = (parse(a), parse(b));
+            assert!(!same(&a, &b), "{a} and {b} should be different");
+            assert_ne!(CanonicalKey::new(&a), CanonicalKey::new(&b));
+        }
+    }
+}
@@ -0,0 +1,389 @@
+use std::collections::HashMap;
+
+use rand::Rng;
+
+use crate::{
+    canonical::same,
+    eval::{eval_f64, eval_with, EvalErr},
+    gen::seeded_rng,
+    parse::{parse_equation, parse_expr, ParseErr},
+    simplify::simplify,
+    Equation, Expr, Func, Op, Pair, Rational,
+};
+
+const SAMPLE_COUNT: usize = 20;
+// relative error allowed between float evaluations of equivalent expressions
+const TOLERANCE: f64 = 1e-9;
+
+/// The rationals that unknowns are sampled from, between `lower` and `upper` with denominators up to
+/// `max_denominator`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Domain {
+    pub lower: i64,
+    pub upper: i64,
+    pub max_denominator: u64,
+}
+
+/// Whether two expressions are equal for every value of their unknowns, where `confidence` is
+/// the share of sampled points that agreed. Identical canonical forms or a point where they
+/// differ are certain. If every point was skipped nothing is known either way, which is reported
+/// as not equivalent with no confidence; see `is_undecided`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub struct Equivalence {
+    pub equivalent: bool,
+    pub confidence: f64,
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Verdict {
+    Equivalent,
+    NotSimplified,
+    Wrong,
+}
+
+impl Default for Domain {
+    fn default() -> Self {
+        Domain {
+            lower: -20,
+            upper: 20,
+            max_denominator: 7,
+        }
+    }
+}
+
+impl Domain {
+    fn rand_point<R: Rng + ?Sized>(&self, rng: &mut R) -> Rational {
+        let denominator = rng.gen_range(1..=self.max_denominator.max(1));
+        let scaled = |bound: i64| bound.saturating_mul(denominator as i64);
+        // the bounds may have been written the wrong way round
+        let (lower, upper) = (self.lower.min(self.upper), self.lower.max(self.upper));
+        let numerator = rng.gen_range(scaled(lower)..=scaled(upper));
+        Rational::new(numerator, denominator)
+    }
+}
+
+impl Equivalence {
+    /// Whether neither side could be evaluated at any sampled point, as opposed to the
+    /// expressions differing at one of them. Both are reported as not equivalent.
+    pub fn is_undecided(&self) -> bool {
+        !self.equivalent && self.confidence == 0.0
+    }
+}
+
+// `None` when either side can't be evaluated at this point
+fn agrees_at(answer: &Expr, expected: &Expr, bindings: &HashMap<char, Rational>) -> Option<bool> {
+    match (eval_with(answer, bindings), eval_with(expected, bindings)) {
+        (Ok(lval), Ok(rval)) => return Some(lval == rval),
+        (Err(EvalErr::NonEvaluableFunc(_) | EvalErr::Irrational), _)
+        | (_, Err(EvalErr::NonEvaluableFunc(_) | EvalErr::Irrational)) => (),
+        _ => return None,
+    }
+
+    // functions and surds can only be compared approximately
+    let bindings = bindings
+        .iter()
+        .map(|(&var, value)| (var, value.numerator as f64 / value.denominator as f64))
+        .collect();
+    let lval = eval_f64(answer, &bindings).ok()?;
+    let rval = eval_f64(expected, &bindings).ok()?;
+    Some((lval - rval).abs() <= TOLERANCE * lval.abs().max(rval.abs()).max(1.0))
+}
+
+/// Checks whether `a` and `b` are mathematically equal, first by comparing their simplified
+/// canonical forms and then by evaluating both exactly at random points in `domain`. Points where
+/// either side can't be evaluated, such as a division by zero, are skipped. The points are the
+/// same on every call, so seeded problem generation stays reproducible.
+pub fn equivalent(a: &Expr, b: &Expr, domain: &Domain) -> Equivalence {
+    let a = simplify(a);
+    let b = simplify(b);
+    if same(&a, &b) {
+        return Equivalence {
+            equivalent: true,
+            confidence: 1.0,
+        };
+    }
+
+    let mut vars = a.variables();
+    vars.extend(
+        b.variables()
+            .into_iter()
+            .filter(|var| !vars.contains(var))
+            .collect::<Vec<_>>(),
+    );
+
+    let mut rng = seeded_rng(0);
+    let mut evaluated = 0;
+    for _ in 0..SAMPLE_COUNT {
+        let bindings = vars
+            .iter()
+            .map(|&var| (var, domain.rand_point(&mut rng)))
+            .collect();
+        match agrees_at(&a, &b, &bindings) {
+            Some(false) => {
+                return Equivalence {
+                    equivalent: false,
+                    confidence: 1.0,
+                }
+            }
+            Some(true) => evaluated += 1,
+            None => (),
+        }
+    }
+
+    Equivalence {
+        equivalent: evaluated > 0,
+        confidence: evaluated as f64 / SAMPLE_COUNT as f64,
+    }
+}
+
+fn is_fraction_literal(expr: &Expr) -> bool {
+    match expr {
+        Expr::Pair(box Pair {
+            left: Expr::Rational(numerator),
+            op: Op::Div,
+            right: Expr::Rational(denominator),
+        }) if numerator.denominator == 1
+            && denominator.denominator == 1
+            && numerator.numerator != 0
+            && denominator.numerator > 1 =>
+        {
+            let denominator = denominator.numerator as u64;
+            Rational::new(numerator.numerator, denominator).denominator == denominator
+        }
+        _ => false,
+    }
+}
+
+// the parser reads `3/4` as a division and `sqrt(3)` as a function, where `simplify` gives a
+// rational and a power of 1/2, and writes a division by a number as a multiplication by its
+// reciprocal. Both sides are put in this form, at any depth, before they're compared
+fn written_form(expr: &Expr) -> Expr {
+    match expr {
+        Expr::Pair(box Pair {
+            left: Expr::Rational(numerator),
+            right: Expr::Rational(denominator),
+            ..
+        }) if is_fraction_literal(expr) => {
+            Rational::new(numerator.numerator, denominator.numerator as u64).into()
+        }
+        Expr::Pair(pair) => {
+            let left = written_form(&pair.left);
+            match (pair.op, written_form(&pair.right)) {
+                (Op::Div, Expr::Rational(divisor)) => match Rational::int(1).checked_div(divisor) {
+                    Ok(reciprocal) => Pair::new(reciprocal.into(), Op::Mul, left).into(),
+                    Err(_) => Pair::new(left, Op::Div, divisor.into()).into(),
+                },
+                (op, right) => Pair::new(left, op, right).into(),
+            }
+        }
+        Expr::Func(Func::Sqrt, inner) => {
+            Pair::new(written_form(inner), Op::Pow, Rational::new(1, 2).into()).into()
+        }
+        Expr::Func(func, inner) => Expr::Func(*func, Box::new(written_form(inner))),
+        Expr::Negative(inner) => Expr::Negative(Box::new(written_form(inner))),
+        Expr::Derivative(inner, var) => Expr::Derivative(Box::new(written_form(inner)), *var),
+        Expr::Rational(_) | Expr::Variable(_) | Expr::Constant(_) => expr.to_owned(),
+    }
+}
+
+fn is_simplified(expr: &Expr) -> bool {
+    same(&written_form(expr), &written_form(&simplify(expr)))
+}
+
+fn verdict(answer: &Expr, expected: &Expr) -> Verdict {
+    if !equivalent(answer, expected, &Domain::default()).equivalent {
+        Verdict::Wrong
+    } else if is_simplified(answer) {
+        Verdict::Equivalent
+    } else {
+        Verdict::NotSimplified
+    }
+}
+
+/// Marks a typed answer such as `-3/4` or `6x^2 - 2cos(x)` against the expected expression.
+pub fn check_answer(input: &str, expected: &Expr) -> Result<Verdict, ParseErr> {
+    Ok(verdict(&parse_expr(input)?, expected))
+}
+
+/// Marks a typed answer such as `x = 2` against an expected equation. A bare value is compared
+/// against the right hand side of the expected equation.
+pub fn check_equation_answer(input: &str, expected: &Equation) -> Result<Verdict, ParseErr> {
+    if !input.contains('=') {
+        return check_answer(input, &expected.rhs);
+    }
+
+    let answer = parse_equation(input)?;
+    if same(&answer.lhs, &expected.lhs) {
+        Ok(verdict(&answer.rhs, &expected.rhs))
+    } else if same(&answer.rhs, &expected.lhs) {
+        Ok(verdict(&answer.lhs, &expected.rhs))
+    } else {
+        Ok(Verdict::Wrong)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::eval::eval_exact;
+
+    #[test]
+    fn marks_fractions() {
+        let expected = Rational::new(-3, 4).into();
+        assert_eq!(check_answer("-3/4", &expected), Ok(Verdict::Equivalent));
+        assert_eq!(check_answer("-6/8", &expected), Ok(Verdict::NotSimplified));
+        assert_eq!(check_answer("3/4", &expected), Ok(Verdict::Wrong));
+    }
+
+    #[test]
+    fn marks_expressions_in_x() {
+        let expected = parse_expr("6*x^2 - 2*cos(x)").unwrap();
+        assert_eq!(
+            check_answer("6x^2 - 2cos(x)", &expected),
+            Ok(Verdict::Equivalent)
+        );
+        assert_eq!(
+            check_answer("-2cos(x) + 6x^2", &expected),
+            Ok(Verdict::Equivalent)
+        );
+        assert_eq!(
+            check_answer("3x^2 + 3x^2 - 2cos(x)", &expected),
+            Ok(Verdict::NotSimplified)
+        );
+        assert_eq!(
+            check_answer("6x^2 + 2cos(x)", &expected),
+            Ok(Verdict::Wrong)
+        );
+    }
+
+    #[test]
+    fn marks_equations() {
+        let expected = parse_equation("x = 2").unwrap();
+        assert_eq!(
+            check_equation_answer("x = 2", &expected),
+            Ok(Verdict::Equivalent)
+        );
+        assert_eq!(
+            check_equation_answer("2 = x", &expected),
+            Ok(Verdict::Equivalent)
+        );
+        assert_eq!(
+            check_equation_answer("2", &expected),
+            Ok(Verdict::Equivalent)
+        );
+        assert_eq!(
+            check_equation_answer("x = 3", &expected),
+            Ok(Verdict::Wrong)
+        );
+    }
+
+    #[test]
+    fn reports_unparseable_answers() {
+        assert!(check_answer("2 +", &Rational::int(2).into()).is_err());
+    }
+
+    #[test]
+    fn marks_fractional_coefficients() {
+        for answer in ["x + 1/2", "3/4*x^2", "3x/4", "x^(1/2)", "-3/4*x"] {
+            let expected = simplify(&parse_expr(answer).unwrap());
+            assert_eq!(
+                check_answer(answer, &expected),
+                Ok(Verdict::Equivalent),
+                "{answer}"
+            );
+        }
+
+        let expected = simplify(&parse_expr("x + 1/2").unwrap());
+        assert_eq!(
+            check_answer("x + 2/4", &expected),
+            Ok(Verdict::NotSimplified)
+        );
+        assert_eq!(check_answer("x + 1/3", &expected), Ok(Verdict::Wrong));
+    }
+
+    #[test]
+    fn marks_exact_surds() {
+        let answer =
+            |question: &str| -> Expr { eval_exact(&parse_expr(question).unwrap()).unwrap().into() };
+
+        let expected = answer("sin(pi/3)");
+        for typed in ["sqrt(3)/2", "1/2*3^(1/2)", "1/2 sqrt(3)"] {
+            assert_eq!(
+                check_answer(typed, &expected),
+                Ok(Verdict::Equivalent),
+                "{typed}"
+            );
+        }
+        assert_eq!(
+            check_answer("sqrt(12)/4", &expected),
+            Ok(Verdict::NotSimplified)
+        );
+        assert_eq!(check_answer("sqrt(2)/2", &expected), Ok(Verdict::Wrong));
+
+        let expected = answer("cos(3pi/4)");
+        assert_eq!(
+            check_answer("-sqrt(2)/2", &expected),
+            Ok(Verdict::Equivalent)
+        );
+
+        let expected = answer("tan(pi/3)");
+        assert_eq!(check_answer("sqrt(3)", &expected), Ok(Verdict::Equivalent));
+        assert_eq!(
+            check_answer("2sqrt(3)", &answer("2tan(pi/3)")),
+            Ok(Verdict::Equivalent)
+        );
+    }
+
+    fn equivalence(a: &str, b: &str, domain: &Domain) -> Equivalence {
+        equivalent(&parse_expr(a).unwrap(), &parse_expr(b).unwrap(), domain)
+    }
+
+    #[test]
+    fn bounds_written_the_wrong_way_round_still_sample() {
+        let domain = Domain {
+            lower: 20,
+            upper: -20,
+            max_denominator: 7,
+        };
+        assert!(equivalence("(x + 1)^2", "x^2 + 2x + 1", &domain).equivalent);
+        assert!(!equivalence("(x + 1)^2", "x^2 + 1", &domain).equivalent);
+
+        let mut rng = seeded_rng(0);
+        for _ in 0..100 {
+            let point = domain.rand_point(&mut rng);
+            assert!(Rational::int(-20) <= point && point <= Rational::int(20));
+        }
+    }
+
+    #[test]
+    fn differing_at_a_point_is_certain() {
+        let result = equivalence("x^2", "x^2 + 1", &Domain::default());
+        assert_eq!(
+            result,
+            Equivalence {
+                equivalent: false,
+                confidence: 1.0,
+            }
+        );
+        assert!(!result.is_undecided());
+    }
+
+    #[test]
+    fn skipping_every_point_is_undecided() {
+        // the only point in the domain is a division by zero on both sides
+        let domain = Domain {
+            lower: 0,
+            upper: 0,
+            max_denominator: 1,
+        };
+        let result = equivalence("1/x", "2/x", &domain);
+        assert_eq!(
+            result,
+            Equivalence {
+                equivalent: false,
+                confidence: 0.0,
+            }
+        );
+        assert!(result.is_undecided());
+    }
+}
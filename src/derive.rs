@@ -1,68 +1,356 @@
-use crate::{Expr, Func, Op, Pair, Rational};
+use crate::{simplify::simplify, Expr, Func, Op, Pair, Rational};
 
-pub fn derive(expr: &Expr) -> Expr {
-    if expr.unknown_count() == 0 {
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Rule {
+    Sum,
+    Difference,
+    Product,
+    Quotient,
+    Power,
+    Exponential,
+    Standard(Func),
+    Chain(Func),
+}
+
+/// One application of a rule, with the derivatives it still depends on left as `Derivative`s.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Step {
+    pub rule: Rule,
+    pub expr: Expr,
+    pub result: Expr,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Trace {
+    pub var: char,
+    pub steps: Vec<Step>,
+    pub result: Expr,
+}
+
+pub fn derive(expr: &Expr, var: char) -> Expr {
+    derive_traced(expr, var, None)
+}
+
+/// Like `derive`, but also records each rule applied, outermost first, for worked solutions.
+pub fn derive_with_steps(expr: &Expr, var: char) -> Trace {
+    let mut steps = vec![];
+    let result = simplify(&derive_traced(expr, var, Some(&mut steps)));
+    Trace { var, steps, result }
+}
+
+fn derive_traced(expr: &Expr, var: char, mut steps: Option<&mut Vec<Step>>) -> Expr {
+    if expr.unknown_count_of(var) == 0 {
         return Rational::int(0).into();
     }
 
     match expr {
-        Expr::Rational(_) => Rational::int(0).into(),
-        Expr::Pair(pair) => match pair.op {
-            Op::Add | Op::Sub => {
-                Pair::new(derive(&pair.left), pair.op.clone(), derive(&pair.right)).into()
+        Expr::Rational(_) | Expr::Constant(_) => Rational::int(0).into(),
+        Expr::Negative(expr) => Expr::Negative(Box::new(derive_traced(&expr, var, steps))),
+        Expr::Variable(unknown) if *unknown == var => Rational::int(1).into(),
+        Expr::Variable(_) => Rational::int(0).into(),
+        Expr::Derivative(expr, wrt) => derive_traced(&derive(&expr, *wrt), var, steps),
+        Expr::Pair(_) | Expr::Func(..) => {
+            if let Some(steps) = steps.as_deref_mut() {
+                let (rule, result) = apply_rule(expr, var, &mut |part| {
+                    Expr::Derivative(Box::new(part.to_owned()), var)
+                });
+                steps.push(Step {
+                    rule,
+                    expr: expr.to_owned(),
+                    result,
+                });
             }
-            Op::Mul => Pair::new(
-                Pair::new(derive(&pair.left), Op::Mul, pair.right.clone()).into(),
-                Op::Add,
-                Pair::new(pair.left.clone(), Op::Mul, derive(&pair.right)).into(),
-            )
-            .into(),
-            Op::Div => Pair::new(
+
+            apply_rule(expr, var, &mut |part| {
+                derive_traced(part, var, steps.as_deref_mut())
+            })
+            .1
+        }
+    }
+}
+
+// differentiates one level of `expr`, using `derive` for the derivatives of its parts
+fn apply_rule(expr: &Expr, var: char, derive: &mut dyn FnMut(&Expr) -> Expr) -> (Rule, Expr) {
+    match expr {
+        Expr::Pair(pair) => match pair.op {
+            Op::Add | Op::Sub => (
+                if pair.op == Op::Add {
+                    Rule::Sum
+                } else {
+                    Rule::Difference
+                },
+                Pair::new(derive(&pair.left), pair.op.clone(), derive(&pair.right)).into(),
+            ),
+            Op::Mul => (
+                Rule::Product,
                 Pair::new(
                     Pair::new(derive(&pair.left), Op::Mul, pair.right.clone()).into(),
-                    Op::Sub,
+                    Op::Add,
                     Pair::new(pair.left.clone(), Op::Mul, derive(&pair.right)).into(),
                 )
                 .into(),
-                Op::Div,
-                Pair::new(pair.right.clone(), Op::Pow, Rational::int(2).into()).into(),
-            )
-            .into(),
-            Op::Pow => {
-                if pair.right.unknown_count() == 0 {
+            ),
+            Op::Div => (
+                Rule::Quotient,
+                Pair::new(
                     Pair::new(
+                        Pair::new(derive(&pair.left), Op::Mul, pair.right.clone()).into(),
+                        Op::Sub,
+                        Pair::new(pair.left.clone(), Op::Mul, derive(&pair.right)).into(),
+                    )
+                    .into(),
+                    Op::Div,
+                    Pair::new(pair.right.clone(), Op::Pow, Rational::int(2).into()).into(),
+                )
+                .into(),
+            ),
+            Op::Pow => {
+                if pair.right.unknown_count_of(var) == 0 {
+                    (
+                        Rule::Power,
                         Pair::new(
-                            pair.right.clone(),
+                            Pair::new(
+                                pair.right.clone(),
+                                Op::Mul,
+                                Pair::new(
+                                    pair.left.clone(),
+                                    Op::Pow,
+                                    Pair::new(pair.right.clone(), Op::Sub, Rational::int(1).into())
+                                        .into(),
+                                )
+                                .into(),
+                            )
+                            .into(),
                             Op::Mul,
+                            derive(&pair.left),
+                        )
+                        .into(),
+                    )
+                } else if pair.left.unknown_count_of(var) == 0 {
+                    // a^g = e^(g ln(a)), so its derivative is a^g ln(a) g'
+                    (
+                        Rule::Exponential,
+                        Pair::new(
                             Pair::new(
-                                pair.left.clone(),
-                                Op::Pow,
-                                Pair::new(pair.right.clone(), Op::Sub, Rational::int(1).into())
-                                    .into(),
+                                expr.clone(),
+                                Op::Mul,
+                                Expr::Func(Func::Ln, Box::new(pair.left.clone())),
                             )
                             .into(),
+                            Op::Mul,
+                            derive(&pair.right),
                         )
                         .into(),
-                        Op::Mul,
-                        derive(&pair.left),
                     )
-                    .into()
                 } else {
-                    unimplemented!();
+                    // f^g = e^(g ln(f)), so its derivative is f^g (g ln(f))'
+                    let exponent: Expr = Pair::new(
+                        pair.right.clone(),
+                        Op::Mul,
+                        Expr::Func(Func::Ln, Box::new(pair.left.clone())),
+                    )
+                    .into();
+                    (
+                        Rule::Exponential,
+                        Pair::new(expr.clone(), Op::Mul, derive(&exponent)).into(),
+                    )
                 }
             }
         },
-        Expr::Negative(expr) => Expr::Negative(Box::new(derive(&expr))),
-        Expr::Variable(_) => Rational::int(1).into(),
-        Expr::Derivative(expr) => derive(&derive(&expr)),
-        Expr::Func(func, inner) => Pair::new(
-            match func {
-                Func::Sine => Expr::Func(Func::Cosine, inner.to_owned()),
-                Func::Cosine => Expr::Negative(Box::new(Expr::Func(Func::Sine, inner.to_owned()))),
-            },
-            Op::Mul,
-            derive(&*inner),
+        Expr::Func(func, inner) => {
+            let rule = if **inner == Expr::Variable(var) {
+                Rule::Standard(*func)
+            } else {
+                Rule::Chain(*func)
+            };
+            (
+                rule,
+                Pair::new(derive_func(*func, inner), Op::Mul, derive(inner)).into(),
+            )
+        }
+        _ => unreachable!("only pairs and functions have rules"),
+    }
+}
+
+// the derivative of `func` with respect to its argument, to be multiplied by the argument's
+// derivative
+fn derive_func(func: Func, inner: &Expr) -> Expr {
+    let inner = inner.to_owned();
+    let reciprocal =
+        |expr: Expr| -> Expr { Pair::new(Rational::int(1).into(), Op::Div, expr).into() };
+    let one_minus_square = || -> Expr {
+        Pair::new(
+            Rational::int(1).into(),
+            Op::Sub,
+            Pair::new(inner.clone(), Op::Pow, Rational::int(2).into()).into(),
+        )
+        .into()
+    };
+
+    match func {
+        Func::Sine => Expr::Func(Func::Cosine, Box::new(inner)),
+        Func::Cosine => Expr::Negative(Box::new(Expr::Func(Func::Sine, Box::new(inner)))),
+        Func::Tan => reciprocal(
+            Pair::new(
+                Expr::Func(Func::Cosine, Box::new(inner)),
+                Op::Pow,
+                Rational::int(2).into(),
+            )
+            .into(),
+        ),
+        Func::Exp => Expr::Func(Func::Exp, Box::new(inner)),
+        Func::Ln => reciprocal(inner),
+        Func::Log(base) => reciprocal(
+            Pair::new(
+                inner,
+                Op::Mul,
+                Expr::Func(Func::Ln, Box::new(Rational::int(base as i64).into())),
+            )
+            .into(),
+        ),
+        Func::Sqrt => reciprocal(
+            Pair::new(
+                Rational::int(2).into(),
+                Op::Mul,
+                Expr::Func(Func::Sqrt, Box::new(inner)),
+            )
+            .into(),
+        ),
+        Func::Abs => Pair::new(
+            inner.clone(),
+            Op::Div,
+            Expr::Func(Func::Abs, Box::new(inner)),
         )
         .into(),
+        Func::Arcsin => reciprocal(Expr::Func(Func::Sqrt, Box::new(one_minus_square()))),
+        Func::Arccos => Expr::Negative(Box::new(reciprocal(Expr::Func(
+            Func::Sqrt,
+            Box::new(one_minus_square()),
+        )))),
+        Func::Arctan => reciprocal(
+            Pair::new(
+                Rational::int(1).into(),
+                Op::Add,
+                Pair::new(inner, Op::Pow, Rational::int(2).into()).into(),
+            )
+            .into(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        check::{equivalent, Domain},
+        gen::{gen_derivable, seeded_rng},
+        parse::parse_expr,
+        render::LatexConvertible,
+    };
+
+    fn assert_derivative(expr: &str, var: char, expected: &str) {
+        let derivative = derive(&parse_expr(expr).unwrap(), var);
+        let expected = parse_expr(expected).unwrap();
+        assert!(
+            equivalent(&derivative, &expected, &Domain::default()).equivalent,
+            "d/d{} {} gave {}",
+            var,
+            expr,
+            derivative
+        );
+    }
+
+    #[test]
+    fn differentiates_with_respect_to_the_given_variable() {
+        assert_derivative("x*y^2", 'y', "2*x*y");
+        assert_derivative("x*y^2", 'x', "y^2");
+        assert_derivative("3*y + 2", 'x', "0");
+        assert_eq!(
+            derive(&parse_expr("y^3").unwrap(), 'x'),
+            Rational::int(0).into()
+        );
+    }
+
+    #[test]
+    fn differentiates_every_function() {
+        assert_derivative("tan(x)", 'x', "1/cos(x)^2");
+        assert_derivative("exp(x)", 'x', "exp(x)");
+        assert_derivative("ln(x)", 'x', "1/x");
+        assert_derivative("log(x)", 'x', "1/(x*ln(10))");
+        assert_derivative("sqrt(x)", 'x', "1/(2*sqrt(x))");
+        assert_derivative("abs(x)", 'x', "x/abs(x)");
+        assert_derivative("arcsin(x/30)", 'x', "1/(30*sqrt(1 - (x/30)^2))");
+        assert_derivative("arccos(x/30)", 'x', "-1/(30*sqrt(1 - (x/30)^2))");
+        assert_derivative("arctan(x)", 'x', "1/(1 + x^2)");
+    }
+
+    #[test]
+    fn applies_the_chain_rule_to_functions() {
+        assert_derivative("ln(x^2 + 1)", 'x', "2*x/(x^2 + 1)");
+        assert_derivative("sqrt(3*x + 1)", 'x', "3/(2*sqrt(3*x + 1))");
+        assert_derivative("exp(sin(x))", 'x', "cos(x)*exp(sin(x))");
+    }
+
+    #[test]
+    fn differentiates_variable_exponents() {
+        assert_derivative("2^x", 'x', "2^x*ln(2)");
+        assert_derivative("e^(3*x)", 'x', "3*e^(3*x)");
+        assert_derivative("x^x", 'x', "x^x*(ln(x) + 1)");
+        assert_derivative(
+            "(x^2 + 1)^sin(x)",
+            'x',
+            "(x^2 + 1)^sin(x)*(cos(x)*ln(x^2 + 1) + 2*x*sin(x)/(x^2 + 1))",
+        );
+    }
+
+    #[test]
+    fn differentiates_generated_expressions() {
+        for seed in 0..200 {
+            let expr = gen_derivable(&mut seeded_rng(seed), 3);
+            derive(&expr, 'x');
+            derive_with_steps(&expr, 'x');
+        }
+    }
+
+    #[test]
+    fn records_each_rule_outermost_first() {
+        let expr = parse_expr("x^2*sin(x)").unwrap();
+        let trace = derive_with_steps(&expr, 'x');
+        let rules: Vec<Rule> = trace.steps.iter().map(|step| step.rule).collect();
+        assert_eq!(
+            rules,
+            vec![Rule::Product, Rule::Power, Rule::Standard(Func::Sine)]
+        );
+        assert_eq!(trace.steps[0].expr, expr);
+        assert_eq!(trace.result, simplify(&derive(&expr, 'x')));
+
+        let rules: Vec<Rule> = derive_with_steps(&parse_expr("sin(3*x)/x").unwrap(), 'x')
+            .steps
+            .iter()
+            .map(|step| step.rule)
+            .collect();
+        assert_eq!(rules[..2], [Rule::Quotient, Rule::Chain(Func::Sine)]);
+    }
+
+    #[test]
+    fn constants_need_no_steps() {
+        let trace = derive_with_steps(&parse_expr("5*y").unwrap(), 'x');
+        assert!(trace.steps.is_empty());
+        assert_eq!(trace.result, Rational::int(0).into());
+    }
+
+    #[test]
+    fn traces_are_written_as_text_and_latex() {
+        let trace = derive_with_steps(&parse_expr("x^2*sin(x)").unwrap(), 'x');
+        let text = trace.to_string();
+        assert!(text.starts_with("ddx(x ^ (2) * sin(x)) = "));
+        assert!(text.contains("(product rule)"));
+        assert!(text.ends_with(&format!("= {}", trace.result)));
+
+        let latex = trace.to_latex();
+        assert!(latex.starts_with("\\begin{aligned} \\frac{d}{dx}"));
+        assert!(latex.contains("&& \\text{power rule}"));
+        assert!(latex.ends_with("\\end{aligned}"));
+        assert_eq!(latex.matches(" \\\\ ").count(), trace.steps.len());
     }
 }
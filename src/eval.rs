@@ -1,137 +1,375 @@
-use std::{
-    mem::swap,
-    ops::{Add, Div, Mul, Neg, Sub},
-};
+use std::collections::HashMap;
 
-use crate::{derive::derive, Expr, Func, Op, Rational};
+use crate::{derive::derive, Constant, ExactVal, Expr, Func, Multiple, Op, Rational, Surd};
 
-fn lcm(a: u64, b: u64) -> u64 {
-    let mut guess = if a > b { a } else { b };
-
-    loop {
-        if guess % a == 0 && guess % b == 0 {
-            return guess;
-        }
-
-        guess += 1;
-    }
-}
-
-fn gcd(a: u64, b: u64) -> u64 {
-    let mut max = a;
-    let mut min = b;
-    if min > max {
-        swap(&mut max, &mut min);
-    }
-
-    loop {
-        let rem = max % min;
-        if rem == 0 {
-            return min;
-        }
-
-        max = min;
-        min = rem;
+pub(crate) fn gcd(a: u128, b: u128) -> u128 {
+    let mut a = a;
+    let mut b = b;
+    while b != 0 {
+        (a, b) = (b, a % b);
     }
+    a
 }
 
 impl Rational {
     pub fn simplified(&self) -> Rational {
         if self.numerator == 0 {
-            return Rational::new(0, 1);
+            return Rational::int(0);
         }
 
-        let divisor = gcd(self.numerator.abs() as u64, self.denominator);
+        let divisor = gcd(
+            self.numerator.unsigned_abs() as u128,
+            self.denominator as u128,
+        );
         if divisor == 1 {
             self.to_owned()
         } else {
             Rational {
                 numerator: self.numerator / (divisor as i64),
-                denominator: self.denominator / divisor,
+                denominator: self.denominator / (divisor as u64),
             }
         }
     }
-}
 
-impl Mul for Rational {
-    type Output = Rational;
+    // every product of a numerator and a denominator fits in 128 bits, so overflow can only
+    // happen when summing those products or when narrowing the reduced result
+    fn from_wide(numerator: i128, denominator: u128) -> Result<Rational, EvalErr> {
+        if numerator == 0 {
+            return Ok(Rational::int(0));
+        }
+
+        let divisor = gcd(numerator.unsigned_abs(), denominator);
+        let numerator = numerator / (divisor as i128);
+        let denominator = denominator / divisor;
+
+        Ok(Rational {
+            numerator: i64::try_from(numerator).map_err(|_| EvalErr::Overflow)?,
+            denominator: u64::try_from(denominator).map_err(|_| EvalErr::Overflow)?,
+        })
+    }
+
+    pub fn checked_add(self, rhs: Rational) -> Result<Rational, EvalErr> {
+        let lnumerator = self.numerator as i128 * rhs.denominator as i128;
+        let rnumerator = rhs.numerator as i128 * self.denominator as i128;
+        Rational::from_wide(
+            lnumerator
+                .checked_add(rnumerator)
+                .ok_or(EvalErr::Overflow)?,
+            self.denominator as u128 * rhs.denominator as u128,
+        )
+    }
+
+    pub fn checked_sub(self, rhs: Rational) -> Result<Rational, EvalErr> {
+        self.checked_add(rhs.checked_neg()?)
+    }
+
+    pub fn checked_mul(self, rhs: Rational) -> Result<Rational, EvalErr> {
+        Rational::from_wide(
+            self.numerator as i128 * rhs.numerator as i128,
+            self.denominator as u128 * rhs.denominator as u128,
+        )
+    }
 
-    fn mul(self, rhs: Self) -> Self::Output {
-        Rational {
-            numerator: self.numerator * rhs.numerator,
-            denominator: self.denominator * rhs.denominator,
+    pub fn checked_div(self, rhs: Rational) -> Result<Rational, EvalErr> {
+        if rhs.numerator == 0 {
+            return Err(EvalErr::DivisionByZero);
         }
-        .simplified()
+
+        Rational::from_wide(
+            self.numerator as i128 * rhs.denominator as i128 * rhs.numerator.signum() as i128,
+            self.denominator as u128 * rhs.numerator.unsigned_abs() as u128,
+        )
+    }
+
+    pub fn checked_neg(self) -> Result<Rational, EvalErr> {
+        Ok(Rational {
+            numerator: self.numerator.checked_neg().ok_or(EvalErr::Overflow)?,
+            denominator: self.denominator,
+        })
     }
 }
 
-impl Add for Rational {
-    type Output = Rational;
+impl Rational {
+    pub fn checked_pow(self, exponent: i64) -> Result<Rational, EvalErr> {
+        if exponent < 0 {
+            if self.numerator == 0 {
+                return Err(EvalErr::DivisionByZero);
+            }
+            return Rational::int(1)
+                .checked_div(self.checked_pow(exponent.checked_neg().ok_or(EvalErr::Overflow)?)?);
+        }
 
-    fn add(self, rhs: Self) -> Self::Output {
-        let new_denom = lcm(self.denominator, rhs.denominator);
-        let lmult = (new_denom / self.denominator) as i64;
-        let rmult = (new_denom / rhs.denominator) as i64;
-        Rational {
-            numerator: self.numerator * lmult + rhs.numerator * rmult,
-            denominator: new_denom,
+        let mut result = Rational::int(1);
+        let mut base = self;
+        let mut exponent = exponent;
+        while exponent > 0 {
+            if exponent % 2 == 1 {
+                result = result.checked_mul(base)?;
+            }
+            exponent /= 2;
+            if exponent > 0 {
+                base = base.checked_mul(base)?;
+            }
         }
-        .simplified()
+
+        Ok(result)
     }
-}
 
-impl Neg for Rational {
-    type Output = Rational;
+    pub fn pow(self, exponent: Rational) -> Result<ExactVal, EvalErr> {
+        if exponent.denominator == 0 {
+            return Err(EvalErr::DivisionByZero);
+        }
+        let exponent = exponent.simplified();
+        let power = self.checked_pow(exponent.numerator)?;
+        if exponent.denominator == 1 {
+            return Ok(ExactVal::Rational(power));
+        }
 
-    fn neg(self) -> Self::Output {
-        Rational {
-            numerator: -self.numerator,
-            denominator: self.denominator,
+        let index = u32::try_from(exponent.denominator).map_err(|_| EvalErr::Overflow)?;
+        power.root(index)
+    }
+
+    pub fn root(self, index: u32) -> Result<ExactVal, EvalErr> {
+        if self.numerator < 0 && index.is_multiple_of(2) {
+            return Err(EvalErr::NonReal);
         }
+
+        // (a/b)^(1/n) = (a * b^(n - 1))^(1/n) / b keeps the root out of the denominator
+        let denominator = i64::try_from(self.denominator).map_err(|_| EvalErr::Overflow)?;
+        let denominator_power = Rational::int(denominator)
+            .checked_pow(index as i64 - 1)?
+            .numerator;
+        let radicand = self
+            .numerator
+            .unsigned_abs()
+            .checked_mul(denominator_power as u64)
+            .ok_or(EvalErr::Overflow)?;
+        let coefficient = Rational::new(self.numerator.signum(), self.denominator);
+
+        ExactVal::surd(coefficient, radicand, index)
     }
 }
 
-impl Sub for Rational {
-    type Output = Rational;
+// past this, only a perfect power check is made on what remains under the root
+const MAX_TRIAL_FACTOR: u64 = 1 << 16;
+
+fn integer_root(n: u64, index: u32) -> Option<u64> {
+    let guess = (n as f64).powf(1.0 / index as f64).round() as u64;
+    (guess.saturating_sub(1)..=(guess + 1)).find(|root| root.checked_pow(index) == Some(n))
+}
+
+fn extract_power(radicand: u64, index: u32) -> (u64, u64) {
+    let mut outside = 1;
+    let mut inside = radicand;
 
-    fn sub(self, rhs: Self) -> Self::Output {
-        self + -rhs
+    let mut factor: u64 = 2;
+    while factor <= MAX_TRIAL_FACTOR {
+        let power = match factor.checked_pow(index) {
+            Some(power) if power <= inside => power,
+            _ => break,
+        };
+        while inside.is_multiple_of(power) {
+            inside /= power;
+            outside *= factor;
+        }
+        factor += 1;
+    }
+
+    if let Some(root) = integer_root(inside, index) {
+        outside *= root;
+        inside = 1;
     }
+
+    (outside, inside)
 }
 
-impl Div for Rational {
-    type Output = Rational;
+impl ExactVal {
+    pub fn surd(coefficient: Rational, radicand: u64, index: u32) -> Result<ExactVal, EvalErr> {
+        // a zeroth root is a power of 1/0
+        if index == 0 {
+            return Err(EvalErr::DivisionByZero);
+        }
+        if coefficient.numerator == 0 || radicand == 0 {
+            return Ok(ExactVal::Rational(Rational::int(0)));
+        }
 
-    fn div(self, rhs: Self) -> Self::Output {
-        let denom_sign = rhs.numerator.signum();
+        let (outside, inside) = if index == 1 {
+            (radicand, 1)
+        } else {
+            extract_power(radicand, index)
+        };
+        let coefficient = coefficient.checked_mul(Rational::int(
+            i64::try_from(outside).map_err(|_| EvalErr::Overflow)?,
+        ))?;
 
-        Rational {
-            numerator: self.numerator * (rhs.denominator as i64) * denom_sign,
-            denominator: self.denominator * (rhs.numerator.abs() as u64),
+        if inside == 1 {
+            return Ok(ExactVal::Rational(coefficient));
+        }
+        if inside > i64::MAX as u64 {
+            return Err(EvalErr::Overflow);
         }
-        .simplified()
+
+        Ok(ExactVal::Surd(Surd {
+            coefficient,
+            radicand: inside,
+            index,
+        }))
     }
-}
 
-impl Rational {
-    fn pow(self, exponent: Rational) -> Rational {
-        if exponent.denominator != 1 {
-            unimplemented!("Fractional exponents not yet implemented");
+    pub fn multiple(coefficient: Rational, constant: Constant) -> ExactVal {
+        if coefficient.numerator == 0 {
+            ExactVal::Rational(Rational::int(0))
+        } else {
+            ExactVal::Multiple(Multiple {
+                coefficient,
+                constant,
+            })
         }
+    }
 
-        if exponent.numerator == 0 {
-            return Rational::int(1);
+    fn coefficient(self) -> Rational {
+        match self {
+            ExactVal::Rational(rational) => rational,
+            ExactVal::Surd(surd) => surd.coefficient,
+            ExactVal::Multiple(multiple) => multiple.coefficient,
         }
+    }
 
-        let mut result = self;
-        for _ in 0..exponent.numerator.abs() {
-            result = result * exponent;
+    // multiples of constants can't be combined with surds, so they have no parts
+    fn parts(self) -> Result<(Rational, u64, u32), EvalErr> {
+        match self {
+            ExactVal::Rational(rational) => Ok((rational, 1, 1)),
+            ExactVal::Surd(surd) => Ok((surd.coefficient, surd.radicand, surd.index)),
+            ExactVal::Multiple(_) => Err(EvalErr::Irrational),
         }
+    }
 
-        if exponent.numerator > 0 {
-            result
+    pub fn checked_add(self, rhs: ExactVal) -> Result<ExactVal, EvalErr> {
+        if self.coefficient().numerator == 0 {
+            return Ok(rhs);
+        } else if rhs.coefficient().numerator == 0 {
+            return Ok(self);
+        }
+        if let (ExactVal::Multiple(left), ExactVal::Multiple(right)) = (self, rhs) {
+            if left.constant == right.constant {
+                return Ok(ExactVal::multiple(
+                    left.coefficient.checked_add(right.coefficient)?,
+                    left.constant,
+                ));
+            }
+        }
+
+        let (lcoefficient, lradicand, lindex) = self.parts()?;
+        let (rcoefficient, rradicand, rindex) = rhs.parts()?;
+        if lradicand == rradicand && lindex == rindex {
+            ExactVal::surd(lcoefficient.checked_add(rcoefficient)?, lradicand, lindex)
         } else {
-            Rational::int(1) / result
+            Err(EvalErr::Irrational)
+        }
+    }
+
+    pub fn checked_neg(self) -> Result<ExactVal, EvalErr> {
+        if let ExactVal::Multiple(multiple) = self {
+            return Ok(ExactVal::multiple(
+                multiple.coefficient.checked_neg()?,
+                multiple.constant,
+            ));
+        }
+
+        let (coefficient, radicand, index) = self.parts()?;
+        ExactVal::surd(coefficient.checked_neg()?, radicand, index)
+    }
+
+    pub fn checked_sub(self, rhs: ExactVal) -> Result<ExactVal, EvalErr> {
+        self.checked_add(rhs.checked_neg()?)
+    }
+
+    pub fn checked_mul(self, rhs: ExactVal) -> Result<ExactVal, EvalErr> {
+        match (self, rhs) {
+            (ExactVal::Multiple(multiple), ExactVal::Rational(rational))
+            | (ExactVal::Rational(rational), ExactVal::Multiple(multiple)) => {
+                return Ok(ExactVal::multiple(
+                    multiple.coefficient.checked_mul(rational)?,
+                    multiple.constant,
+                ))
+            }
+            _ => (),
+        }
+
+        let (lcoefficient, lradicand, lindex) = self.parts()?;
+        let (rcoefficient, rradicand, rindex) = rhs.parts()?;
+
+        // bring both roots to a common index before combining them
+        let index = lindex / (gcd(lindex as u128, rindex as u128) as u32) * rindex;
+        let lradicand = lradicand
+            .checked_pow(index / lindex)
+            .ok_or(EvalErr::Overflow)?;
+        let rradicand = rradicand
+            .checked_pow(index / rindex)
+            .ok_or(EvalErr::Overflow)?;
+
+        ExactVal::surd(
+            lcoefficient.checked_mul(rcoefficient)?,
+            lradicand.checked_mul(rradicand).ok_or(EvalErr::Overflow)?,
+            index,
+        )
+    }
+
+    pub fn recip(self) -> Result<ExactVal, EvalErr> {
+        let (coefficient, radicand, index) = self.parts()?;
+        if coefficient.numerator == 0 {
+            return Err(EvalErr::DivisionByZero);
+        }
+
+        // 1 / (k m^(1/n)) = m^((n - 1)/n) / (k m)
+        let radicand_power = radicand.checked_pow(index - 1).ok_or(EvalErr::Overflow)?;
+        let radicand = Rational::int(i64::try_from(radicand).map_err(|_| EvalErr::Overflow)?);
+        ExactVal::surd(
+            Rational::int(1).checked_div(coefficient.checked_mul(radicand)?)?,
+            radicand_power,
+            index,
+        )
+    }
+
+    pub fn checked_div(self, rhs: ExactVal) -> Result<ExactVal, EvalErr> {
+        match (self, rhs) {
+            (ExactVal::Multiple(left), ExactVal::Multiple(right))
+                if left.constant == right.constant =>
+            {
+                Ok(ExactVal::Rational(
+                    left.coefficient.checked_div(right.coefficient)?,
+                ))
+            }
+            (ExactVal::Multiple(multiple), ExactVal::Rational(rational)) => Ok(ExactVal::multiple(
+                multiple.coefficient.checked_div(rational)?,
+                multiple.constant,
+            )),
+            _ => self.checked_mul(rhs.recip()?),
+        }
+    }
+
+    pub fn pow(self, exponent: ExactVal) -> Result<ExactVal, EvalErr> {
+        let exponent = match exponent {
+            ExactVal::Rational(exponent) => exponent,
+            ExactVal::Surd(_) | ExactVal::Multiple(_) => return Err(EvalErr::Irrational),
+        };
+
+        match self {
+            ExactVal::Rational(rational) => rational.pow(exponent),
+            ExactVal::Multiple(_) if exponent == Rational::int(0) => {
+                Ok(ExactVal::Rational(Rational::int(1)))
+            }
+            ExactVal::Multiple(_) if exponent == Rational::int(1) => Ok(self),
+            ExactVal::Multiple(_) => Err(EvalErr::Irrational),
+            ExactVal::Surd(surd) => {
+                // (k m^(1/n))^e = k^e * m^(e/n)
+                let radicand =
+                    Rational::int(i64::try_from(surd.radicand).map_err(|_| EvalErr::Overflow)?);
+                let radical_exponent = exponent.checked_div(Rational::int(surd.index as i64))?;
+                surd.coefficient
+                    .pow(exponent)?
+                    .checked_mul(radicand.pow(radical_exponent)?)
+            }
         }
     }
 }
@@ -140,31 +378,469 @@ impl Rational {
 pub enum EvalErr {
     EncounteredUnknown(char),
     NonEvaluableFunc(Func),
+    Overflow,
+    DivisionByZero,
+    NonReal,
+    Irrational,
+}
+
+fn eval_small(expr: &Expr, vars: &HashMap<char, Rational>) -> Result<ExactVal, EvalErr> {
+    let answer = match expr {
+        Expr::Rational(rational) if rational.denominator == 0 => {
+            return Err(EvalErr::DivisionByZero)
+        }
+        Expr::Rational(rational) => ExactVal::Rational(rational.simplified()),
+        Expr::Pair(pair) => {
+            let lval = eval_small(&pair.left, vars)?;
+            let rval = eval_small(&pair.right, vars)?;
+
+            match pair.op {
+                Op::Add => lval.checked_add(rval)?,
+                Op::Sub => lval.checked_sub(rval)?,
+                Op::Mul => lval.checked_mul(rval)?,
+                Op::Div => lval.checked_div(rval)?,
+                Op::Pow => lval.pow(rval)?,
+            }
+        }
+        Expr::Negative(expr) => {
+            let val = eval_small(expr, vars)?;
+            val.checked_neg()?
+        }
+        Expr::Variable(unknown) => match vars.get(unknown) {
+            Some(value) => ExactVal::Rational(value.simplified()),
+            None => return Err(EvalErr::EncounteredUnknown(unknown.to_owned())),
+        },
+        Expr::Constant(constant) => ExactVal::multiple(Rational::int(1), *constant),
+        Expr::Derivative(expr, var) => eval_small(&derive(expr, *var), vars)?,
+        Expr::Func(func, inner) => func.apply_exact(eval_small(inner, vars)?)?,
+    };
+
+    Ok(answer)
+}
+
+pub fn eval_exact_with(expr: &Expr, vars: &HashMap<char, Rational>) -> Result<ExactVal, EvalErr> {
+    match eval_small(expr, vars) {
+        // intermediate values may not fit even though the answer does
+        #[cfg(feature = "big-rational")]
+        Err(EvalErr::Overflow) => Ok(ExactVal::Rational(
+            crate::big::eval_big(expr, vars)?.try_into()?,
+        )),
+        result => result,
+    }
+}
+
+pub fn eval_exact(expr: &Expr) -> Result<ExactVal, EvalErr> {
+    eval_exact_with(expr, &HashMap::new())
+}
+
+pub fn eval_with(expr: &Expr, vars: &HashMap<char, Rational>) -> Result<Rational, EvalErr> {
+    match eval_exact_with(expr, vars)? {
+        ExactVal::Rational(rational) => Ok(rational),
+        ExactVal::Surd(_) | ExactVal::Multiple(_) => Err(EvalErr::Irrational),
+    }
 }
 
 pub fn eval(expr: &Expr) -> Result<Rational, EvalErr> {
+    eval_with(expr, &HashMap::new())
+}
+
+impl Constant {
+    pub fn value(&self) -> f64 {
+        match self {
+            Constant::Pi => std::f64::consts::PI,
+            Constant::E => std::f64::consts::E,
+        }
+    }
+}
+
+impl Func {
+    pub fn apply(&self, x: f64) -> f64 {
+        match self {
+            Func::Sine => x.sin(),
+            Func::Cosine => x.cos(),
+            Func::Tan => x.tan(),
+            Func::Exp => x.exp(),
+            Func::Ln if x == 0.0 => f64::NAN,
+            Func::Ln => x.ln(),
+            Func::Log(_) if x == 0.0 => f64::NAN,
+            Func::Log(base) => x.log(*base as f64),
+            Func::Sqrt => x.sqrt(),
+            Func::Abs => x.abs(),
+            Func::Arcsin => x.asin(),
+            Func::Arccos => x.acos(),
+            Func::Arctan => x.atan(),
+        }
+    }
+
+    // the few values which are rational or surds, such as `sqrt(8)` or `log_2(1/4)`
+    fn apply_exact(&self, val: ExactVal) -> Result<ExactVal, EvalErr> {
+        let zero = Rational::int(0);
+        let one = Rational::int(1);
+
+        match self {
+            Func::Abs if val.coefficient() < zero => return val.checked_neg(),
+            Func::Abs => return Ok(val),
+            Func::Sqrt => return val.pow(ExactVal::Rational(Rational::new(1, 2))),
+            _ => (),
+        }
+        let rational = match val {
+            ExactVal::Rational(rational) => rational,
+            ExactVal::Multiple(Multiple {
+                coefficient,
+                constant: Constant::Pi,
+            }) => return self.apply_at_pi_multiple(coefficient),
+            ExactVal::Multiple(Multiple {
+                coefficient,
+                constant: Constant::E,
+            }) if *self == Func::Ln && coefficient == one => return Ok(ExactVal::Rational(one)),
+            _ => return Err(EvalErr::NonEvaluableFunc(*self)),
+        };
+
+        let answer = match self {
+            Func::Ln | Func::Log(_) if rational <= zero => return Err(EvalErr::NonReal),
+            Func::Arcsin | Func::Arccos
+                if rational.numerator.unsigned_abs() > rational.denominator =>
+            {
+                return Err(EvalErr::NonReal)
+            }
+            Func::Sine | Func::Tan | Func::Arcsin | Func::Arctan if rational == zero => zero,
+            Func::Cosine | Func::Exp if rational == zero => one,
+            Func::Exp if rational == one => return Ok(ExactVal::multiple(one, Constant::E)),
+            Func::Ln | Func::Arccos if rational == one => zero,
+            Func::Log(base) => {
+                log_exact(*base, rational).ok_or(EvalErr::NonEvaluableFunc(*self))?
+            }
+            func => return Err(EvalErr::NonEvaluableFunc(*func)),
+        };
+
+        Ok(ExactVal::Rational(answer))
+    }
+
+    // trig functions of multiples of 30 and 45 degrees
+    fn apply_at_pi_multiple(&self, coefficient: Rational) -> Result<ExactVal, EvalErr> {
+        let twelfths = coefficient.checked_mul(Rational::int(12))?;
+        if twelfths.denominator != 1 || (twelfths.numerator % 2 != 0 && twelfths.numerator % 3 != 0)
+        {
+            return Err(EvalErr::NonEvaluableFunc(*self));
+        }
+
+        let degrees = twelfths.numerator.rem_euclid(24) * 15;
+        let (quadrant, reference) = (degrees / 90, degrees % 90);
+        let sin = sin_of_reference_angle(reference)?;
+        let cos = sin_of_reference_angle(90 - reference)?;
+        let (sin, cos) = match quadrant {
+            0 => (sin, cos),
+            1 => (cos, sin.checked_neg()?),
+            2 => (sin.checked_neg()?, cos.checked_neg()?),
+            _ => (cos.checked_neg()?, sin),
+        };
+
+        match self {
+            Func::Sine => Ok(sin),
+            Func::Cosine => Ok(cos),
+            Func::Tan if cos == ExactVal::Rational(Rational::int(0)) => {
+                Err(EvalErr::DivisionByZero)
+            }
+            Func::Tan => sin.checked_div(cos),
+            func => Err(EvalErr::NonEvaluableFunc(*func)),
+        }
+    }
+}
+
+fn sin_of_reference_angle(degrees: i64) -> Result<ExactVal, EvalErr> {
+    match degrees {
+        0 => Ok(ExactVal::Rational(Rational::int(0))),
+        30 => Ok(ExactVal::Rational(Rational::new(1, 2))),
+        45 => ExactVal::surd(Rational::new(1, 2), 2, 2),
+        60 => ExactVal::surd(Rational::new(1, 2), 3, 2),
+        90 => Ok(ExactVal::Rational(Rational::int(1))),
+        _ => unreachable!("reference angles are multiples of 30 or 45 degrees"),
+    }
+}
+
+// the integer `k` with `base^k == rational`, if there is one
+fn log_exact(base: u64, rational: Rational) -> Option<Rational> {
+    if base < 2 {
+        return None;
+    }
+
+    let (mut remaining, sign) = if rational.numerator == 1 {
+        (rational.denominator, -1)
+    } else if rational.denominator == 1 {
+        (u64::try_from(rational.numerator).ok()?, 1)
+    } else {
+        return None;
+    };
+
+    let mut exponent = 0;
+    while remaining > 1 {
+        if !remaining.is_multiple_of(base) {
+            return None;
+        }
+        remaining /= base;
+        exponent += 1;
+    }
+
+    Some(Rational::int(sign * exponent))
+}
+
+// `powf` is NaN for any negative base, but odd roots of negative numbers are real
+pub(crate) fn pow_f64(base: f64, exponent: f64, exact_exponent: Option<Rational>) -> f64 {
+    match exact_exponent {
+        Some(exponent) if base < 0.0 && exponent.denominator % 2 == 1 => {
+            let magnitude = (-base).powf(exponent.numerator as f64 / exponent.denominator as f64);
+            if exponent.numerator % 2 == 0 {
+                magnitude
+            } else {
+                -magnitude
+            }
+        }
+        _ => base.powf(exponent),
+    }
+}
+
+fn check_f64(val: f64) -> Result<f64, EvalErr> {
+    if val.is_nan() {
+        Err(EvalErr::NonReal)
+    } else if val.is_infinite() {
+        Err(EvalErr::Overflow)
+    } else {
+        Ok(val)
+    }
+}
+
+/// Approximates `expr` with floats, so unlike `eval` it can handle functions.
+pub fn eval_f64(expr: &Expr, vars: &HashMap<char, f64>) -> Result<f64, EvalErr> {
     let answer = match expr {
-        Expr::Rational(rational) => rational.to_owned(),
+        Expr::Rational(rational) if rational.denominator == 0 => {
+            return Err(EvalErr::DivisionByZero)
+        }
+        Expr::Rational(rational) => rational.numerator as f64 / rational.denominator as f64,
         Expr::Pair(pair) => {
-            let lval = eval(&pair.left)?;
-            let rval = eval(&pair.right)?;
+            let lval = eval_f64(&pair.left, vars)?;
+            let rval = eval_f64(&pair.right, vars)?;
 
             match pair.op {
                 Op::Add => lval + rval,
                 Op::Sub => lval - rval,
                 Op::Mul => lval * rval,
+                Op::Div if rval == 0.0 => return Err(EvalErr::DivisionByZero),
                 Op::Div => lval / rval,
-                Op::Pow => lval.pow(rval),
+                Op::Pow if lval == 0.0 && rval < 0.0 => return Err(EvalErr::DivisionByZero),
+                Op::Pow => pow_f64(lval, rval, eval(&pair.right).ok()),
             }
         }
-        Expr::Negative(expr) => {
-            let val = eval(expr)?;
-            -val
-        }
-        Expr::Variable(unknown) => return Err(EvalErr::EncounteredUnknown(unknown.to_owned())),
-        Expr::Derivative(expr) => eval(&derive(&expr))?,
-        Expr::Func(func, _) => return Err(EvalErr::NonEvaluableFunc(func.to_owned())),
+        Expr::Negative(expr) => -eval_f64(expr, vars)?,
+        Expr::Variable(unknown) => match vars.get(unknown) {
+            Some(value) => *value,
+            None => return Err(EvalErr::EncounteredUnknown(unknown.to_owned())),
+        },
+        Expr::Constant(constant) => constant.value(),
+        Expr::Derivative(expr, var) => eval_f64(&derive(expr, *var), vars)?,
+        Expr::Func(func, inner) => func.apply(eval_f64(inner, vars)?),
     };
 
-    Ok(answer.simplified())
+    check_f64(answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_expr;
+
+    fn rational_power(base: i64, numerator: i64, denominator: u64) -> Result<ExactVal, EvalErr> {
+        Rational::int(base).pow(Rational::new(numerator, denominator))
+    }
+
+    #[test]
+    fn fractional_powers_are_exact() {
+        assert_eq!(
+            rational_power(8, 2, 3).ok(),
+            Some(ExactVal::Rational(Rational::int(4)))
+        );
+        assert_eq!(
+            rational_power(12, 1, 2).ok(),
+            ExactVal::surd(Rational::int(2), 3, 2).ok()
+        );
+        assert_eq!(
+            rational_power(4, -1, 2).ok(),
+            Some(ExactVal::Rational(Rational::new(1, 2)))
+        );
+    }
+
+    #[test]
+    fn divisions_by_zero_are_errors() {
+        assert!(matches!(
+            Rational::int(1).checked_div(Rational::int(0)),
+            Err(EvalErr::DivisionByZero)
+        ));
+        for input in ["1/0", "0/0", "1/(3 - 3)", "2 + 1/(1/2 - 1/2)"] {
+            assert!(
+                matches!(
+                    eval_exact(&parse_expr(input).unwrap()),
+                    Err(EvalErr::DivisionByZero)
+                ),
+                "{input}"
+            );
+        }
+        let undefined = Rational {
+            numerator: 1,
+            denominator: 0,
+        };
+        assert!(matches!(
+            eval_exact(&undefined.into()),
+            Err(EvalErr::DivisionByZero)
+        ));
+        assert!(matches!(
+            eval_with(
+                &parse_expr("x/(x - 2)").unwrap(),
+                &HashMap::from([('x', Rational::int(2))])
+            ),
+            Err(EvalErr::DivisionByZero)
+        ));
+    }
+
+    #[test]
+    fn powers_which_divide_by_zero_are_errors() {
+        assert!(matches!(
+            rational_power(0, -1, 1),
+            Err(EvalErr::DivisionByZero)
+        ));
+        assert!(matches!(
+            rational_power(8, 1, 0),
+            Err(EvalErr::DivisionByZero)
+        ));
+        assert!(matches!(
+            rational_power(8, 0, 0),
+            Err(EvalErr::DivisionByZero)
+        ));
+        assert!(matches!(
+            ExactVal::surd(Rational::int(1), 8, 0),
+            Err(EvalErr::DivisionByZero)
+        ));
+    }
+
+    #[test]
+    fn variables_are_bound_by_name() {
+        let expr = parse_expr("x*y + 1/x").unwrap();
+        let vars = HashMap::from([('x', Rational::int(2)), ('y', Rational::new(1, 4))]);
+        assert_eq!(eval_with(&expr, &vars).ok(), Some(Rational::int(1)));
+        assert!(matches!(
+            eval_with(&expr, &HashMap::from([('x', Rational::int(2))])),
+            Err(EvalErr::EncounteredUnknown('y'))
+        ));
+        assert!(matches!(
+            eval_with(
+                &expr,
+                &HashMap::from([('x', Rational::int(0)), ('y', Rational::int(1))])
+            ),
+            Err(EvalErr::DivisionByZero)
+        ));
+    }
+
+    #[test]
+    fn floats_evaluate_every_function() {
+        let cases = [
+            ("sin(pi/6)", 0.5),
+            ("cos(pi)", -1.0),
+            ("tan(pi/4)", 1.0),
+            ("exp(1)", std::f64::consts::E),
+            ("ln(e^2)", 2.0),
+            ("log(1000)", 3.0),
+            ("sqrt(x)", 1.5),
+            ("abs(-x)", 2.25),
+            ("arcsin(1)", std::f64::consts::FRAC_PI_2),
+            ("arccos(1)", 0.0),
+            ("arctan(1)", std::f64::consts::FRAC_PI_4),
+        ];
+        let vars = HashMap::from([('x', 2.25)]);
+        for (expr, expected) in cases {
+            let value = eval_f64(&parse_expr(expr).unwrap(), &vars).unwrap();
+            assert!((value - expected).abs() < 1e-12, "{} gave {}", expr, value);
+        }
+    }
+
+    #[test]
+    fn floats_outside_a_domain_are_errors() {
+        let vars = HashMap::from([('x', -1.0)]);
+        for expr in ["sqrt(x)", "ln(x)", "arcsin(2)"] {
+            assert!(
+                eval_f64(&parse_expr(expr).unwrap(), &vars).is_err(),
+                "{}",
+                expr
+            );
+        }
+        assert!(matches!(
+            eval_f64(&parse_expr("1/(x + 1)").unwrap(), &vars),
+            Err(EvalErr::DivisionByZero)
+        ));
+    }
+
+    #[test]
+    fn constants_stay_exact_as_multiples() {
+        let exact = |input: &str| eval_exact(&parse_expr(input).unwrap()).ok();
+        assert_eq!(
+            exact("3*pi/4"),
+            Some(ExactVal::multiple(Rational::new(3, 4), Constant::Pi))
+        );
+        assert_eq!(
+            exact("pi + pi"),
+            Some(ExactVal::multiple(Rational::int(2), Constant::Pi))
+        );
+        assert_eq!(
+            exact("2*e"),
+            Some(ExactVal::multiple(Rational::int(2), Constant::E))
+        );
+        assert_eq!(exact("pi - pi"), Some(ExactVal::Rational(Rational::int(0))));
+        assert!(matches!(
+            eval_exact(&parse_expr("pi*e").unwrap()),
+            Err(EvalErr::Irrational)
+        ));
+    }
+
+    #[test]
+    fn constants_evaluate_as_floats() {
+        let vars = HashMap::new();
+        let float = |input: &str| eval_f64(&parse_expr(input).unwrap(), &vars).unwrap();
+        assert_eq!(float("pi"), std::f64::consts::PI);
+        assert_eq!(float("e"), std::f64::consts::E);
+        assert!((float("pi/e") - std::f64::consts::PI / std::f64::consts::E).abs() < 1e-12);
+    }
+
+    #[test]
+    fn trig_functions_of_standard_angles_are_exact() {
+        let exact = |input: &str| eval_exact(&parse_expr(input).unwrap()).ok();
+        let surd = |numerator: i64, denominator: u64, radicand: u64| {
+            ExactVal::surd(Rational::new(numerator, denominator), radicand, 2).ok()
+        };
+        assert_eq!(exact("sin(pi/3)"), surd(1, 2, 3));
+        assert_eq!(exact("cos(pi/4)"), surd(1, 2, 2));
+        assert_eq!(exact("cos(3*pi/4)"), surd(-1, 2, 2));
+        assert_eq!(exact("tan(pi/6)"), surd(1, 3, 3));
+        assert_eq!(exact("tan(2*pi/3)"), surd(-1, 1, 3));
+        assert_eq!(exact("sin(7*pi/6)"), surd(-1, 2, 1));
+        assert_eq!(exact("cos(-pi/3)"), surd(1, 2, 1));
+        assert_eq!(exact("sin(pi/2)"), surd(1, 1, 1));
+        assert_eq!(exact("cos(pi)"), surd(-1, 1, 1));
+        assert_eq!(exact("tan(5*pi/4)"), surd(1, 1, 1));
+    }
+
+    #[test]
+    fn trig_functions_of_other_angles_are_not_exact() {
+        assert!(matches!(
+            eval_exact(&parse_expr("tan(pi/2)").unwrap()),
+            Err(EvalErr::DivisionByZero)
+        ));
+        assert!(eval_exact(&parse_expr("sin(pi/5)").unwrap()).is_err());
+        assert!(eval_exact(&parse_expr("sin(2)").unwrap()).is_err());
+    }
+
+    #[test]
+    fn even_roots_of_negatives_are_not_real() {
+        assert!(matches!(rational_power(-4, 1, 2), Err(EvalErr::NonReal)));
+        assert_eq!(
+            rational_power(-8, 1, 3).ok(),
+            Some(ExactVal::Rational(Rational::int(-2)))
+        );
+    }
 }
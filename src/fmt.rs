@@ -1,4 +1,11 @@
-use crate::{Equation, Expr, Op, Pair, Rational, Func};
+use crate::{
+    derive::{Rule, Trace},
+    inequality::{Range, RangeSet},
+    integrate::Integral,
+    solve::{Reason, Root, Solution},
+    Comparison, Constant, Equation, ExactVal, Expr, Func, Multiple, Op, Pair, Rational, Relation,
+    Surd, System,
+};
 use std::fmt::Display;
 
 pub fn bracketize(s: &str) -> String {
@@ -40,7 +47,16 @@ impl Display for Func {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
             Func::Sine => "sin",
-            Func::Cosine => "cos"
+            Func::Cosine => "cos",
+            Func::Tan => "tan",
+            Func::Exp => "exp",
+            Func::Ln => "ln",
+            Func::Log(base) => return f.write_str(&format!("log_{base}")),
+            Func::Sqrt => "sqrt",
+            Func::Abs => "abs",
+            Func::Arcsin => "arcsin",
+            Func::Arccos => "arccos",
+            Func::Arctan => "arctan",
         };
         f.write_str(s)
     }
@@ -53,8 +69,9 @@ impl Display for Expr {
             Expr::Pair(pair) => f.write_str(&format!("{}", pair)),
             Expr::Negative(expr) => f.write_str(&format!("-{}", expr)),
             Expr::Variable(var) => f.write_str(&String::from(var.to_owned())),
-            Expr::Derivative(expr) => f.write_str(&format!("ddx({})", expr)),
+            Expr::Derivative(expr, var) => f.write_str(&format!("dd{}({})", var, expr)),
             Expr::Func(func, inner) => f.write_str(&format!("{func}({inner})")),
+            Expr::Constant(constant) => f.write_str(&format!("{constant}")),
         }
     }
 }
@@ -65,6 +82,33 @@ impl Display for Equation {
     }
 }
 
+impl Display for Relation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Relation::Equal => "=",
+            Relation::NotEqual => "≠",
+            Relation::Less => "<",
+            Relation::LessEqual => "≤",
+            Relation::Greater => ">",
+            Relation::GreaterEqual => "≥",
+        };
+        f.write_str(s)
+    }
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{} {} {}", self.lhs, self.relation, self.rhs))
+    }
+}
+
+impl Display for System {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let equations: Vec<String> = self.equations.iter().map(|e| e.to_string()).collect();
+        f.write_str(&equations.join(", "))
+    }
+}
+
 impl Display for Rational {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         if self.denominator == 1 {
@@ -74,3 +118,146 @@ impl Display for Rational {
         }
     }
 }
+
+impl Display for Surd {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{}", Into::<Expr>::into(*self)))
+    }
+}
+
+impl Display for ExactVal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExactVal::Rational(rational) => f.write_str(&format!("{}", rational)),
+            ExactVal::Surd(surd) => f.write_str(&format!("{}", surd)),
+            ExactVal::Multiple(multiple) => f.write_str(&format!("{}", multiple)),
+        }
+    }
+}
+
+impl Display for Integral {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.bounds {
+            Some((lower, upper)) => f.write_str(&format!(
+                "∫[{}, {}] {} d{}",
+                lower, upper, self.integrand, self.var
+            )),
+            None => f.write_str(&format!("∫ {} d{}", self.integrand, self.var)),
+        }
+    }
+}
+
+impl Display for Constant {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Constant::Pi => "π",
+            Constant::E => "e",
+        };
+        f.write_str(s)
+    }
+}
+
+impl Display for Multiple {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&format!("{}", Into::<Expr>::into(*self)))
+    }
+}
+
+impl Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Rule::Sum => "sum rule",
+            Rule::Difference => "difference rule",
+            Rule::Product => "product rule",
+            Rule::Quotient => "quotient rule",
+            Rule::Power => "power rule",
+            Rule::Exponential => "exponential rule",
+            Rule::Standard(func) => return f.write_str(&format!("derivative of {func}")),
+            Rule::Chain(func) => return f.write_str(&format!("chain rule, {func}")),
+        };
+        f.write_str(s)
+    }
+}
+
+impl Display for Trace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for step in &self.steps {
+            writeln!(
+                f,
+                "dd{}({}) = {}    ({})",
+                self.var, step.expr, step.result, step.rule
+            )?;
+        }
+        write!(f, "= {}", self.result)
+    }
+}
+
+impl Display for Reason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Reason::SwapSides => f.write_str("swap the sides"),
+            Reason::Negate => f.write_str("multiply both sides by -1"),
+            Reason::Add(expr) => write!(f, "add {expr} to both sides"),
+            Reason::Subtract(expr) => write!(f, "subtract {expr} from both sides"),
+            Reason::Multiply(expr) => write!(f, "multiply both sides by {expr}"),
+            Reason::Divide(expr) => write!(f, "divide both sides by {expr}"),
+            Reason::SubtractFrom(expr) => write!(f, "subtract both sides from {expr}"),
+            Reason::DivideInto(expr) => write!(f, "divide {expr} by both sides"),
+            Reason::Root(expr) => write!(f, "raise both sides to the power 1/({expr})"),
+            Reason::Differentiate(var) => write!(f, "differentiate with respect to {var}"),
+            Reason::CollectTerms => f.write_str("collect like terms"),
+            Reason::Evaluate => f.write_str("evaluate"),
+        }
+    }
+}
+
+impl Display for Solution {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.equation)?;
+        for step in &self.steps {
+            write!(f, "\n{}    ({})", step.equation, step.reason)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for Root {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Root::Exact(expr) => write!(f, "{expr}"),
+            Root::Approximate(value) => write!(f, "≈ {value:.6}"),
+        }
+    }
+}
+
+impl Display for Range {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.is_point() {
+            if let Some(point) = &self.lower {
+                return write!(f, "{{{}}}", point.value);
+            }
+        }
+
+        match &self.lower {
+            Some(lower) if lower.closed => write!(f, "[{}", lower.value)?,
+            Some(lower) => write!(f, "({}", lower.value)?,
+            None => f.write_str("(-∞")?,
+        }
+        match &self.upper {
+            Some(upper) if upper.closed => write!(f, ", {}]", upper.value),
+            Some(upper) => write!(f, ", {})", upper.value),
+            None => f.write_str(", ∞)"),
+        }
+    }
+}
+
+impl Display for RangeSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.ranges.is_empty() {
+            return f.write_str("∅");
+        }
+
+        let ranges: Vec<String> = self.ranges.iter().map(|r| r.to_string()).collect();
+        f.write_str(&ranges.join(" ∪ "))
+    }
+}
@@ -1,40 +1,86 @@
-use rand::{self, seq::SliceRandom, Rng};
-
-use crate::{simplify::simplify, Equation, Expr, Func, Op, Pair, Rational};
+use std::collections::{HashMap, HashSet};
+
+use rand::{self, seq::SliceRandom, Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use crate::{
+    canonical::CanonicalKey,
+    check::{equivalent, Domain},
+    derive::derive,
+    eval::{eval_exact, EvalErr},
+    inequality::{solve_inequality, RangeSet},
+    integrate::Integral,
+    polynomial::Polynomial,
+    simplify::simplify,
+    solve::{solve, solve_system},
+    Comparison, Constant, Equation, ExactVal, Expr, Func, Op, Pair, Rational, Relation, System,
+};
+
+/// ChaCha is used because its output is stable across platforms and releases, so a seed is
+/// enough to regenerate a problem.
+pub type SeededRng = ChaCha8Rng;
+
+pub fn seeded_rng(seed: u64) -> SeededRng {
+    SeededRng::seed_from_u64(seed)
+}
 
 fn pair(l: Expr, op: Op, r: Expr) -> Expr {
     Pair::new(l, op, r).into()
 }
 
-pub fn rand_int() -> Rational {
-    let mut rng = rand::thread_rng();
+// trial division any further than this would take seconds for the largest integers
+const MAX_TRIAL_DIVISOR: i64 = 1 << 16;
+
+pub fn rand_int<R: Rng + ?Sized>(rng: &mut R) -> Rational {
     let n: i64 = rng.gen_range(1..10);
     Rational::int(n)
 }
 
-pub fn rand_rational() -> Rational {
-    let mut rng = rand::thread_rng();
+pub fn rand_rational<R: Rng + ?Sized>(rng: &mut R) -> Rational {
     let numerator: i64 = rng.gen_range(1..35);
     let denominator: u64 = rng.gen_range(1..10);
     Rational::new(numerator, denominator)
 }
 
+/// The positive divisors of a positive integer, in increasing order. Every divisor is found for
+/// integers below 2^32, while larger ones may miss those made up of two prime factors above
+/// `MAX_TRIAL_DIVISOR`.
 pub fn factors(n: Rational) -> Vec<Rational> {
-    if n.denominator != 1 {
+    if n.denominator != 1 || n.numerator < 1 {
         return vec![];
     }
 
-    let numerators = (1..(n.numerator + 1))
-        .into_iter()
-        .filter(|&x| n.numerator % x == 0)
-        .collect::<Vec<i64>>();
-    numerators
-        .into_iter()
-        .map(|numerator| Rational::int(numerator))
-        .collect()
+    // every divisor is a product of the prime factors, which are found by trial division up to the
+    // square root of whatever is left to factorise
+    let mut divisors = vec![1];
+    let mut remaining = n.numerator;
+    let mut prime: i64 = 2;
+    while prime <= MAX_TRIAL_DIVISOR
+        && prime
+            .checked_mul(prime)
+            .is_some_and(|square| square <= remaining)
+    {
+        let smaller = divisors.clone();
+        let mut power = 1;
+        while remaining % prime == 0 {
+            remaining /= prime;
+            power *= prime;
+            divisors.extend(smaller.iter().map(|divisor| divisor * power));
+        }
+        prime += 1;
+    }
+    // whatever is left is a prime, or a product of large primes treated as if it were one
+    if remaining > 1 {
+        let smaller = divisors.clone();
+        divisors.extend(smaller.iter().map(|divisor| divisor * remaining));
+    }
+
+    divisors.sort();
+    divisors.into_iter().map(Rational::int).collect()
 }
 
-pub fn gen<Rand: Fn() -> Rational>(
+pub fn gen<R: Rng + ?Sized, Rand: Fn(&mut R) -> Rational>(
+    rng: &mut R,
     depth: u64,
     answer: Rational,
     rand_term: &Rand,
@@ -43,12 +89,15 @@ pub fn gen<Rand: Fn() -> Rational>(
     if depth == 0 {
         answer.into()
     } else {
-        let mut rng = rand::thread_rng();
         let mut ops = vec![Op::Add, Op::Sub, Op::Mul, Op::Div];
         if let Some(previous_op) = previous_op {
             ops = ops.into_iter().filter(|&op| op != previous_op).collect();
         }
-        let mut op = ops[rng.gen_range(0..(ops.len()))];
+        // the divisor of `l / r` is `l / answer`, which is undefined for a zero answer
+        if answer.numerator == 0 {
+            ops.retain(|&op| op != Op::Div);
+        }
+        let mut op = *ops.choose(rng).unwrap();
         let l = match op {
             Op::Mul => {
                 let nice_numbers: Vec<Rational> = factors(answer)
@@ -56,48 +105,66 @@ pub fn gen<Rand: Fn() -> Rational>(
                     .filter(|&factor| factor != Rational::int(1) && factor != answer)
                     .collect();
                 if nice_numbers.len() == 0 {
-                    return gen(depth, answer, rand_term, previous_op);
+                    return gen(rng, depth, answer, rand_term, previous_op);
                 }
-                nice_numbers[rng.gen_range(0..(nice_numbers.len()))]
+                Ok(*nice_numbers.choose(rng).unwrap())
             }
             Op::Add | Op::Sub => {
-                let mut l = rand_term();
+                let l = rand_term(rng);
                 if l >= answer {
-                    l = l + Rational::int(1);
                     op = Op::Sub;
+                    l.checked_add(Rational::int(1))
                 } else {
                     op = Op::Add;
+                    Ok(l)
+                }
+            }
+            Op::Div => {
+                let divisor = rand_term(rng);
+                if divisor.numerator == 0 {
+                    return gen(rng, depth, answer, rand_term, previous_op);
                 }
-                l
+                divisor.checked_mul(answer)
             }
-            Op::Div => rand_term() * answer,
             Op::Pow => unreachable!(),
         };
 
-        let r = match op {
-            Op::Add => answer - l,
-            Op::Sub => l - answer,
-            Op::Mul => answer / l,
-            Op::Div => l / answer,
-            Op::Pow => unreachable!(),
+        let operands = l.and_then(|l| {
+            let r = match op {
+                Op::Add => answer.checked_sub(l),
+                Op::Sub => l.checked_sub(answer),
+                Op::Mul => answer.checked_div(l),
+                Op::Div => l.checked_div(answer),
+                Op::Pow => unreachable!(),
+            }?;
+            Ok((l, r))
+        });
+        // an answer too large to split up is left as it is
+        let Ok((l, r)) = operands else {
+            return answer.into();
         };
-        let lexpr = gen(depth - 1, l, rand_term, Some(op));
-        let rexpr = gen(depth - 1, r, rand_term, Some(op));
+        let lexpr = gen(rng, depth - 1, l, rand_term, Some(op));
+        let rexpr = gen(rng, depth - 1, r, rand_term, Some(op));
         let value = pair(lexpr, op, rexpr);
         value
     }
 }
 
-pub fn gen_arithmetic(depth: u64, answer: Rational) -> Expr {
-    gen(depth, answer, &|| rand_int(), None)
+pub fn gen_arithmetic<R: Rng + ?Sized>(rng: &mut R, depth: u64, answer: Rational) -> Expr {
+    gen(rng, depth, answer, &|rng| rand_int(rng), None)
 }
 
 #[derive(Debug)]
 enum GenErr {
     UnexpectedVariable,
+    UnexpectedConstant,
 }
 
-fn replace_random_constant(expr: &mut Expr, replacement: Expr) -> Result<Rational, GenErr> {
+fn replace_random_constant<R: Rng + ?Sized>(
+    rng: &mut R,
+    expr: &mut Expr,
+    replacement: Expr,
+) -> Result<Rational, GenErr> {
     match expr {
         Expr::Rational(rational) => {
             let rational = rational.clone();
@@ -105,53 +172,235 @@ fn replace_random_constant(expr: &mut Expr, replacement: Expr) -> Result<Rationa
             Ok(rational.to_owned())
         }
         Expr::Variable(_) => Err(GenErr::UnexpectedVariable),
+        Expr::Constant(_) => Err(GenErr::UnexpectedConstant),
         Expr::Pair(pair) => {
-            let mut rng = rand::thread_rng();
             if rng.gen_bool(0.5) {
-                replace_random_constant(&mut pair.left, replacement)
+                replace_random_constant(rng, &mut pair.left, replacement)
             } else {
-                replace_random_constant(&mut pair.right, replacement)
+                replace_random_constant(rng, &mut pair.right, replacement)
             }
         }
-        Expr::Derivative(inner) | Expr::Negative(inner) | Expr::Func(_, inner) => {
-            replace_random_constant(&mut *inner, replacement)
+        Expr::Derivative(inner, _) | Expr::Negative(inner) | Expr::Func(_, inner) => {
+            replace_random_constant(rng, &mut *inner, replacement)
+        }
+    }
+}
+
+// `c x + d`, with `d` written as a generated expression `depth` deep
+fn linear_side<R: Rng + ?Sized>(
+    rng: &mut R,
+    depth: u64,
+    coefficient: Rational,
+    constant: Rational,
+) -> Result<Expr, EvalErr> {
+    let term = pair(coefficient.into(), Op::Mul, Expr::Variable('x'));
+    Ok(if constant.numerator == 0 {
+        term
+    } else if constant.numerator < 0 {
+        let constant = gen(rng, depth, constant.checked_neg()?, &rand_int, None);
+        pair(term, Op::Sub, constant)
+    } else {
+        let constant = gen(rng, depth, constant, &rand_int, None);
+        pair(term, Op::Add, constant)
+    })
+}
+
+/// An equation in `x` along with its solution, made by replacing one of the numbers in a
+/// generated expression with `x`. With `both_sides`, the right hand side is written as `c x + d`
+/// too, so that the like terms have to be collected before the equation can be undone.
+pub fn gen_backtrack<R: Rng + ?Sized>(
+    rng: &mut R,
+    depth: u64,
+    both_sides: bool,
+) -> (Equation, Rational) {
+    loop {
+        let rhs = rand_int(rng);
+        let mut lhs = gen(rng, depth, rhs, &|rng| rand_int(rng), None);
+
+        let replaced_term = replace_random_constant(rng, &mut lhs, Expr::Variable('x'))
+            .expect("Generated expr shouldn't contain variable yet");
+        if !both_sides {
+            return (
+                Equation {
+                    lhs,
+                    rhs: rhs.into(),
+                },
+                replaced_term,
+            );
+        }
+
+        // `c x + d` equals the right hand side at the solution
+        let coefficient = rand_int(rng);
+        let rhs = coefficient
+            .checked_mul(replaced_term)
+            .and_then(|cx| rhs.checked_sub(cx))
+            .and_then(|constant| linear_side(rng, depth, coefficient, constant));
+        let Ok(rhs) = rhs else {
+            continue;
+        };
+        let equation = Equation { lhs, rhs };
+
+        // `x` can end up in a divisor, or with the same coefficient on both sides, neither of
+        // which has this one solution
+        if solve(&equation).is_ok_and(|solution| solution == replaced_term) {
+            return (equation, replaced_term);
         }
     }
 }
 
-pub fn gen_backtrack(depth: u64) -> (Equation, Rational) {
-    let rhs = rand_int();
-    let mut lhs = gen(depth, rhs, &|| rand_int(), None);
+// `a x + b y + ...`, written the way it would be by hand
+fn linear_sum(coefficients: &[i64], vars: &[char]) -> Expr {
+    let mut sum: Option<Expr> = None;
+    for (&coefficient, &var) in coefficients.iter().zip(vars) {
+        let magnitude = coefficient.abs();
+        let term = if magnitude == 1 {
+            Expr::Variable(var)
+        } else {
+            pair(
+                Rational::int(magnitude).into(),
+                Op::Mul,
+                Expr::Variable(var),
+            )
+        };
+
+        sum = match sum {
+            _ if coefficient == 0 => sum,
+            None if coefficient < 0 => Some(Expr::Negative(Box::new(term))),
+            None => Some(term),
+            Some(sum) if coefficient < 0 => Some(pair(sum, Op::Sub, term)),
+            Some(sum) => Some(pair(sum, Op::Add, term)),
+        };
+    }
+    sum.unwrap_or_else(|| Rational::int(0).into())
+}
 
-    let replaced_term = replace_random_constant(&mut lhs, Expr::Variable('x'))
-        .expect("Generated expr shouldn't contain variable yet");
+const SYSTEM_VARS: [char; 4] = ['x', 'y', 'z', 'w'];
+
+/// Simultaneous linear equations in `var_count` unknowns, with a unique integer solution.
+///
+/// Panics unless `var_count` is between 1 and 4, as there are only names for four unknowns.
+pub fn gen_system<R: Rng + ?Sized>(
+    rng: &mut R,
+    var_count: usize,
+) -> (System, HashMap<char, Rational>) {
+    assert!(
+        (1..=SYSTEM_VARS.len()).contains(&var_count),
+        "A system should have between 1 and {} unknowns, not {var_count}",
+        SYSTEM_VARS.len()
+    );
+    let vars: Vec<char> = SYSTEM_VARS.into_iter().take(var_count).collect();
+    let solution: HashMap<char, Rational> = vars
+        .iter()
+        .map(|&var| (var, Rational::int(rng.gen_range(-9..10))))
+        .collect();
+
+    loop {
+        let equations: Result<Vec<Equation>, EvalErr> = (0..vars.len())
+            .map(|_| {
+                let coefficients: Vec<i64> = vars.iter().map(|_| rng.gen_range(-5..6)).collect();
+                let constant = coefficients.iter().zip(&vars).try_fold(
+                    Rational::int(0),
+                    |sum, (&coefficient, var)| {
+                        sum.checked_add(Rational::int(coefficient).checked_mul(solution[var])?)
+                    },
+                )?;
+
+                Ok(Equation {
+                    lhs: linear_sum(&coefficients, &vars),
+                    rhs: constant.into(),
+                })
+            })
+            .collect();
+        let Ok(equations) = equations else {
+            continue;
+        };
+        let system = System { equations };
 
-    (
-        Equation {
-            lhs,
-            rhs: rhs.into(),
-        },
-        replaced_term,
-    )
+        // random coefficients can leave the equations dependent, or drop an unknown altogether
+        if system.variables().len() == vars.len() && solve_system(&system).is_ok() {
+            return (system, solution);
+        }
+    }
 }
 
-pub fn gen_choices(answer: Rational, count: usize) -> Vec<Rational> {
+/// Wrong answers to a system, each changing one unknown of the real solution.
+pub fn gen_system_choices<R: Rng + ?Sized>(
+    rng: &mut R,
+    answer: &HashMap<char, Rational>,
+    count: usize,
+) -> Vec<HashMap<char, Rational>> {
+    let mut vars: Vec<char> = answer.keys().copied().collect();
+    vars.sort();
+
+    let mut changes: Vec<(char, i64)> = vars
+        .iter()
+        .flat_map(|&var| [-3, -2, -1, 1, 2, 3].map(|offset| (var, offset)))
+        .collect();
+    changes.shuffle(rng);
+
+    let mut choices: Vec<HashMap<char, Rational>> = vec![];
+    for (var, offset) in changes {
+        if choices.len() == count {
+            break;
+        }
+        // an unknown too large to change is left for the others
+        if let Ok(value) = answer[&var].checked_add(Rational::int(offset)) {
+            let mut choice = answer.clone();
+            choice.insert(var, value);
+            choices.push(choice);
+        }
+    }
+    choices
+}
+
+/// An inequality built the same way as `gen_backtrack`'s equations, with its solution.
+pub fn gen_inequality<R: Rng + ?Sized>(rng: &mut R, depth: u64) -> (Comparison, RangeSet) {
+    let relations = [
+        Relation::Less,
+        Relation::LessEqual,
+        Relation::Greater,
+        Relation::GreaterEqual,
+    ];
+
+    loop {
+        let (equation, _) = gen_backtrack(rng, depth, false);
+        let comparison = Comparison {
+            lhs: equation.lhs,
+            relation: *relations.choose(rng).unwrap(),
+            rhs: equation.rhs,
+        };
+
+        // the unknown can end up in a divisor, which isn't a polynomial
+        if let Ok(solution) = solve_inequality(&comparison) {
+            return (comparison, solution);
+        }
+    }
+}
+
+pub fn gen_choices<R: Rng + ?Sized>(rng: &mut R, answer: Rational, count: usize) -> Vec<Rational> {
     let mut answers = vec![];
+    let mut seen = HashSet::from([answer]);
 
     let mut offsets: Vec<_> = (-4..4).filter(|&x| x != 0).collect();
+    offsets.shuffle(rng);
 
-    for _ in 0..count {
-        let mut rng = rand::thread_rng();
-        let index = rng.gen_range(0..offsets.len());
-        let offset = offsets.remove(index);
-        answers.push(answer + Rational::int(offset));
+    for offset in offsets {
+        if answers.len() == count {
+            break;
+        }
+
+        let Ok(choice) = answer.checked_add(Rational::int(offset)) else {
+            continue;
+        };
+        if seen.insert(choice) {
+            answers.push(choice);
+        }
     }
 
     answers
 }
 
-pub fn gen_polynomial(degree: u64) -> Expr {
-    let mut rng = rand::thread_rng();
+pub fn gen_polynomial<R: Rng + ?Sized>(rng: &mut R, degree: u64) -> Expr {
     let mut pair = Pair::new(Rational::int(0).into(), Op::Add, Rational::int(0).into());
     for exponent in (0..=degree).rev() {
         let coefficient = rng.gen_range(-10..10);
@@ -179,25 +428,44 @@ pub fn gen_polynomial(degree: u64) -> Expr {
     simplify(&pair.into())
 }
 
-fn split_x_count(x_count: u64) -> (u64, u64) {
+fn split_x_count<R: Rng + ?Sized>(rng: &mut R, x_count: u64) -> (u64, u64) {
     if x_count == 1 {
-        if rand::thread_rng().gen_bool(0.5) {
+        if rng.gen_bool(0.5) {
             (1, 0)
         } else {
             (0, 1)
         }
     } else {
-        let l = rand::thread_rng().gen_range(1..x_count);
+        let l = rng.gen_range(1..x_count);
         let r = x_count - l;
         (l, r)
     }
 }
 
-fn _gen_derivable(x_count: u64, prev_op: Option<Op>, in_func: bool) -> Expr {
+// abs is left out since its derivative is undefined at zero
+const DERIVABLE_FUNCS: [Func; 10] = [
+    Func::Sine,
+    Func::Cosine,
+    Func::Tan,
+    Func::Exp,
+    Func::Ln,
+    Func::Log(10),
+    Func::Sqrt,
+    Func::Arcsin,
+    Func::Arccos,
+    Func::Arctan,
+];
+
+fn _gen_derivable<R: Rng + ?Sized>(
+    rng: &mut R,
+    x_count: u64,
+    prev_op: Option<Op>,
+    in_func: bool,
+) -> Expr {
     if x_count == 0 {
-        return rand_int().into();
+        return rand_int(rng).into();
     } else if x_count == 1 {
-        let exponent = rand::thread_rng().gen_range(1..=4);
+        let exponent = rng.gen_range(1..=4);
         if exponent == 1 {
             return Expr::Variable('x');
         } else {
@@ -205,15 +473,11 @@ fn _gen_derivable(x_count: u64, prev_op: Option<Op>, in_func: bool) -> Expr {
         }
     }
 
-    let mut rng = rand::thread_rng();
     match rng.gen_range(0..8) {
         0 if !in_func => {
-            let func = [Func::Sine, Func::Cosine]
-                .choose(&mut rng)
-                .unwrap()
-                .to_owned();
+            let func = DERIVABLE_FUNCS.choose(rng).unwrap().to_owned();
 
-            Expr::Func(func, Box::new(_gen_derivable(x_count, None, true)))
+            Expr::Func(func, Box::new(_gen_derivable(rng, x_count, None, true)))
         }
         _ => {
             let mut ops;
@@ -227,18 +491,18 @@ fn _gen_derivable(x_count: u64, prev_op: Option<Op>, in_func: bool) -> Expr {
                 ops = ops.into_iter().filter(|&op| op != prev_op).collect();
             }
 
-            let op = ops.choose(&mut rng).unwrap().to_owned();
-            let (lx_count, rx_count) = split_x_count(x_count);
+            let op = ops.choose(rng).unwrap().to_owned();
+            let (lx_count, rx_count) = split_x_count(rng, x_count);
 
-            let gen = || _gen_derivable(x_count, Some(op), in_func);
-            let gen_left = || _gen_derivable(lx_count, Some(op), in_func);
-            let gen_right = || _gen_derivable(rx_count, Some(op), in_func);
+            let gen = |rng: &mut R| _gen_derivable(rng, x_count, Some(op), in_func);
+            let gen_left = |rng: &mut R| _gen_derivable(rng, lx_count, Some(op), in_func);
+            let gen_right = |rng: &mut R| _gen_derivable(rng, rx_count, Some(op), in_func);
 
             let (left, right) = match op {
-                Op::Add | Op::Sub => (gen_left(), gen_right()),
-                Op::Pow => (gen(), rand_int().into()),
-                Op::Mul => (rand_int().into(), gen()),
-                Op::Div => (gen_left(), gen_right()),
+                Op::Add | Op::Sub => (gen_left(rng), gen_right(rng)),
+                Op::Pow => (gen(rng), rand_int(rng).into()),
+                Op::Mul => (rand_int(rng).into(), gen(rng)),
+                Op::Div => (gen_left(rng), gen_right(rng)),
             };
 
             Pair::new(left, op, right).into()
@@ -246,23 +510,471 @@ fn _gen_derivable(x_count: u64, prev_op: Option<Op>, in_func: bool) -> Expr {
     }
 }
 
-pub fn gen_derivable(x_count: u64) -> Expr {
-    simplify(&_gen_derivable(x_count, None, false))
+pub fn gen_derivable<R: Rng + ?Sized>(rng: &mut R, x_count: u64) -> Expr {
+    simplify(&_gen_derivable(rng, x_count, None, false))
 }
 
-pub fn gen_polynomial_choices(answer: &Expr, degree: u64, count: u64) -> Vec<Expr> {
+/// An indefinite integral question built by differentiating a random antiderivative, which is
+/// returned as the answer with `+ C`.
+pub fn gen_integral<R: Rng + ?Sized>(rng: &mut R, x_count: u64) -> (Integral, Expr) {
+    let antiderivative = gen_derivable(rng, x_count);
+    let integrand = simplify(&derive(&antiderivative, 'x'));
+    let answer = pair(antiderivative, Op::Add, Expr::Variable('C'));
+
+    (Integral::indefinite(integrand, 'x'), answer)
+}
+
+// a choice which can't be evaluated anywhere against the answer or another choice might still be
+// equal to it, so it's only accepted after this many tries, for answers such as those which always
+// overflow and so can't be told apart from anything
+const MAX_UNDECIDED_TRIES: usize = 100;
+// after this many tries at a choice, the choices found so far are all there are
+const MAX_CHOICE_TRIES: usize = 200;
+
+// a wrong answer which is equal to the right one, or to another wrong answer, would give the
+// question more than one correct choice. `seen` holds the canonical forms of the answer and of
+// the choices settled so far, which catches rearrangements without evaluating anything
+fn is_distinct(
+    choice: &Expr,
+    answer: &Expr,
+    choices: &[Expr],
+    seen: &mut HashSet<CanonicalKey>,
+    tries: usize,
+) -> bool {
+    let key = CanonicalKey::new(choice);
+    if seen.contains(&key) {
+        return false;
+    }
+
+    let mut undecided = false;
+    for other in std::iter::once(answer).chain(choices) {
+        let equivalence = equivalent(choice, other, &Domain::default());
+        if equivalence.equivalent {
+            seen.insert(key);
+            return false;
+        }
+        undecided |= equivalence.is_undecided();
+    }
+    // an undecided choice isn't remembered, as it may be accepted on a later try
+    if undecided && tries < MAX_UNDECIDED_TRIES {
+        return false;
+    }
+    seen.insert(key);
+    true
+}
+
+pub fn gen_integral_choices<R: Rng + ?Sized>(
+    rng: &mut R,
+    answer: &Expr,
+    x_count: u64,
+    count: u64,
+) -> Vec<Expr> {
     let mut choices = vec![];
+    // antiderivatives which only differ by a constant are both correct
+    let derivative = |expr: &Expr| derive(expr, 'x');
+    let mut seen = HashSet::from([CanonicalKey::new(&derivative(answer))]);
 
     for _ in 0..count {
-        let mut choice;
-        loop {
-            choice = gen_polynomial(degree);
-            if choice != *answer {
-                break;
+        let derivatives: Vec<Expr> = choices.iter().map(derivative).collect();
+        // there may be fewer distinct antiderivatives of this size than were asked for
+        let Some(choice) = (1..=MAX_CHOICE_TRIES).find_map(|tries| {
+            let choice = pair(gen_derivable(rng, x_count), Op::Add, Expr::Variable('C'));
+            let distinct = is_distinct(
+                &derivative(&choice),
+                &derivative(answer),
+                &derivatives,
+                &mut seen,
+                tries,
+            );
+            distinct.then_some(choice)
+        }) else {
+            break;
+        };
+        choices.push(choice);
+    }
+
+    choices
+}
+
+pub fn gen_polynomial_choices<R: Rng + ?Sized>(
+    rng: &mut R,
+    answer: &Expr,
+    degree: u64,
+    count: u64,
+) -> Vec<Expr> {
+    let mut choices = vec![];
+    let mut seen = HashSet::from([CanonicalKey::new(answer)]);
+
+    for _ in 0..count {
+        // there may be fewer distinct polynomials of this degree than were asked for
+        let Some(choice) = (1..=MAX_CHOICE_TRIES).find_map(|tries| {
+            let choice = gen_polynomial(rng, degree);
+            is_distinct(&choice, answer, &choices, &mut seen, tries).then_some(choice)
+        }) else {
+            break;
+        };
+        choices.push(choice);
+    }
+
+    choices
+}
+
+// `a x + b` with small non-zero integer coefficients
+fn rand_linear<R: Rng + ?Sized>(rng: &mut R, max_a: i64) -> Polynomial {
+    let a = *[-1, 1].choose(rng).unwrap() * rng.gen_range(1..=max_a);
+    let b = *[-1, 1].choose(rng).unwrap() * rng.gen_range(1..10);
+    Polynomial::new('x', vec![Rational::int(b), Rational::int(a)])
+}
+
+/// A product of two brackets to multiply out, along with the expanded polynomial.
+pub fn gen_expand<R: Rng + ?Sized>(rng: &mut R) -> (Expr, Expr) {
+    let left = rand_linear(rng, 3);
+    let (question, right) = if rng.gen_bool(0.2) {
+        (
+            pair(left.clone().into(), Op::Pow, Rational::int(2).into()),
+            left.clone(),
+        )
+    } else {
+        let right = rand_linear(rng, 3);
+        (
+            pair(left.clone().into(), Op::Mul, right.clone().into()),
+            right,
+        )
+    };
+
+    let answer = left
+        .checked_mul(&right)
+        .expect("Product of small linear factors should not overflow");
+    (question, answer.into())
+}
+
+/// A polynomial to factorise, which has either a common factor, two linear factors or is a
+/// difference of two squares, along with its factorised form.
+pub fn gen_factorise<R: Rng + ?Sized>(rng: &mut R) -> (Expr, Expr) {
+    loop {
+        let polynomial = match rng.gen_range(0..3) {
+            0 => {
+                let common = Rational::int(rng.gen_range(2..10));
+                let inner = if rng.gen_bool(0.5) {
+                    Polynomial::identity('x')
+                } else {
+                    Polynomial::constant('x', Rational::int(1))
+                };
+                rand_linear(rng, 5)
+                    .checked_mul(&inner)
+                    .and_then(|p| p.scale(common))
+            }
+            1 => rand_linear(rng, 2).checked_mul(&rand_linear(rng, 1)),
+            _ => {
+                let a = rng.gen_range(1..5);
+                let b = rng.gen_range(1..10);
+                Ok(Polynomial::new(
+                    'x',
+                    vec![
+                        Rational::int(-b * b),
+                        Rational::int(0),
+                        Rational::int(a * a),
+                    ],
+                ))
             }
         }
-        choices.push(choice);
+        .expect("Product of small factors should not overflow");
+
+        let factors = polynomial
+            .factor()
+            .expect("Small polynomial should factorise");
+        // something which doesn't factorise isn't worth asking about
+        if factors.factors.len() == 1
+            && factors.factors[0].1 == 1
+            && factors.constant == Rational::int(1)
+        {
+            continue;
+        }
+        return (polynomial.into(), factors.into());
+    }
+}
+
+/// A `sin`, `cos` or `tan` of a multiple of π/6 or π/4, along with its exact value.
+pub fn gen_exact_trig<R: Rng + ?Sized>(rng: &mut R) -> (Expr, ExactVal) {
+    let twelfths: Vec<i64> = (0..24).filter(|k| k % 2 == 0 || k % 3 == 0).collect();
+
+    loop {
+        let func = *[Func::Sine, Func::Cosine, Func::Tan].choose(rng).unwrap();
+        let angle = ExactVal::multiple(
+            Rational::new(*twelfths.choose(rng).unwrap(), 12),
+            Constant::Pi,
+        );
+        let question = Expr::Func(func, Box::new(angle.into()));
+
+        // tan is undefined at odd multiples of π/2
+        if let Ok(answer) = eval_exact(&question) {
+            return (question, answer);
+        }
+    }
+}
+
+/// Wrong answers for an exact trig question, preferring the answer with the wrong sign and the
+/// value of the complementary function.
+pub fn gen_exact_trig_choices<R: Rng + ?Sized>(
+    rng: &mut R,
+    question: &Expr,
+    count: usize,
+) -> Vec<ExactVal> {
+    let answer = eval_exact(question).expect("Question should have an exact value");
+    let mut likely = vec![answer.checked_neg().ok()];
+    if let Expr::Func(func, angle) = question {
+        let swapped = match func {
+            Func::Sine => eval_exact(&Expr::Func(Func::Cosine, angle.to_owned())),
+            Func::Cosine => eval_exact(&Expr::Func(Func::Sine, angle.to_owned())),
+            _ => answer.recip(),
+        }
+        .ok();
+        likely.push(swapped);
+        likely.push(swapped.and_then(|swapped| swapped.checked_neg().ok()));
+    }
+    likely.shuffle(rng);
+
+    // every value of sin, cos and tan at the standard angles, as `coefficient * sqrt(radicand)`
+    let mut standard: Vec<_> = [
+        (Rational::int(0), 1),
+        (Rational::new(1, 2), 1),
+        (Rational::new(1, 2), 2),
+        (Rational::new(1, 2), 3),
+        (Rational::int(1), 1),
+        (Rational::new(1, 3), 3),
+        (Rational::int(1), 3),
+    ]
+    .into_iter()
+    .flat_map(|(coefficient, radicand)| {
+        let value = ExactVal::surd(coefficient, radicand, 2).ok();
+        [value, value.and_then(|value| value.checked_neg().ok())]
+    })
+    .collect();
+    standard.shuffle(rng);
+
+    let mut choices = vec![];
+    for choice in likely.into_iter().chain(standard).flatten() {
+        if choices.len() == count {
+            break;
+        }
+        if choice != answer && !choices.contains(&choice) {
+            choices.push(choice);
+        }
     }
 
     choices
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{canonical::same, parse::parse_expr};
+
+    #[test]
+    fn same_seed_gives_same_problems() {
+        let generate = |seed: u64| {
+            let mut rng = seeded_rng(seed);
+            let answer = rand_int(&mut rng);
+            let arithmetic = gen(&mut rng, 2, answer, &rand_int, None);
+            let (equation, solution) = gen_backtrack(&mut rng, 2, true);
+            let choices = gen_choices(&mut rng, solution, 3);
+            let polynomial = gen_polynomial(&mut rng, 3);
+            let polynomial_choices = gen_polynomial_choices(&mut rng, &polynomial, 3, 3);
+            let derivable = gen_derivable(&mut rng, 3);
+            (
+                arithmetic,
+                equation,
+                choices,
+                polynomial,
+                polynomial_choices,
+                derivable,
+            )
+        };
+
+        for seed in 0..50 {
+            assert_eq!(generate(seed), generate(seed));
+        }
+    }
+
+    #[test]
+    fn different_seeds_give_different_problems() {
+        let polynomials: HashSet<String> = (0..20)
+            .map(|seed| gen_polynomial(&mut seeded_rng(seed), 3).to_string())
+            .collect();
+        assert!(polynomials.len() > 1);
+    }
+
+    // every divisor in `expr`
+    fn divisors(expr: &Expr, found: &mut Vec<Expr>) {
+        match expr {
+            Expr::Pair(pair) => {
+                if pair.op == Op::Div {
+                    found.push(pair.right.to_owned());
+                }
+                divisors(&pair.left, found);
+                divisors(&pair.right, found);
+            }
+            Expr::Negative(inner) | Expr::Func(_, inner) | Expr::Derivative(inner, _) => {
+                divisors(inner, found)
+            }
+            Expr::Rational(_) | Expr::Variable(_) | Expr::Constant(_) => (),
+        }
+    }
+
+    #[test]
+    fn generated_divisors_are_never_zero() {
+        let zero = Some(ExactVal::Rational(Rational::int(0)));
+        for seed in 0..200 {
+            let mut rng = seeded_rng(seed);
+            let answer = Rational::int(seed as i64 % 5);
+            let exprs = [
+                gen_arithmetic(&mut rng, 3, answer),
+                gen(&mut rng, 3, answer, &|rng| rand_rational(rng), None),
+                gen(&mut rng, 2, answer, &|_| Rational::int(0), None),
+            ];
+
+            for expr in exprs {
+                assert_eq!(eval_exact(&expr).ok(), Some(ExactVal::Rational(answer)));
+                let mut found = vec![];
+                divisors(&expr, &mut found);
+                for divisor in found {
+                    assert_ne!(eval_exact(&divisor).ok(), zero, "{expr}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn fewer_choices_are_given_when_there_are_not_enough() {
+        let mut rng = seeded_rng(0);
+        let (_, answer) = gen_integral(&mut rng, 1);
+        let choices = gen_integral_choices(&mut rng, &answer, 1, 8);
+        assert!(!choices.is_empty() && choices.len() < 8);
+        let derivatives: Vec<Expr> = choices.iter().map(|c| derive(c, 'x')).collect();
+        for (i, derivative) in derivatives.iter().enumerate() {
+            assert!(!same(derivative, &derive(&answer, 'x')));
+            assert!(derivatives[..i]
+                .iter()
+                .all(|other| !same(derivative, other)));
+        }
+
+        // constants from -10 to 9, other than the answer
+        let answer = Rational::int(3).into();
+        let choices = gen_polynomial_choices(&mut rng, &answer, 0, 30);
+        assert_eq!(choices.len(), 19);
+    }
+
+    #[test]
+    fn factors_are_every_divisor() {
+        let factors = |n: i64| -> Vec<i64> {
+            factors(Rational::int(n))
+                .into_iter()
+                .map(|factor| factor.numerator)
+                .collect()
+        };
+        assert_eq!(factors(1), vec![1]);
+        assert_eq!(factors(12), vec![1, 2, 3, 4, 6, 12]);
+        assert_eq!(factors(49), vec![1, 7, 49]);
+        assert_eq!(factors(97), vec![1, 97]);
+        assert_eq!(factors(0), vec![]);
+        assert_eq!(factors(-6), vec![]);
+        assert_eq!(factors(4_294_967_291), vec![1, 4_294_967_291]);
+        // 7^2 * 73 * 127 * 337 * 92737 * 649657, where the last two are found as one
+        assert_eq!(factors(i64::MAX).len(), 3 * 2 * 2 * 2 * 2);
+        assert_eq!(factors(i64::MAX).last(), Some(&i64::MAX));
+        // 2 * 3 * 715827883 * 2147483647, where the last two are found as one
+        let large = factors(i64::MAX - 1);
+        assert!(large.iter().all(|&factor| (i64::MAX - 1) % factor == 0));
+        assert_eq!(&large[..4], &[1, 2, 3, 6]);
+    }
+
+    #[test]
+    fn answers_near_the_limit_do_not_overflow() {
+        let mut rng = seeded_rng(0);
+        for answer in [6, i64::MAX, i64::MAX - 1] {
+            let answer = Rational::int(answer);
+            for _ in 0..50 {
+                let expr = gen(&mut rng, 2, answer, &|_| Rational::int(i64::MAX), None);
+                assert_eq!(eval_exact(&expr).ok(), Some(ExactVal::Rational(answer)));
+                let expr = gen_arithmetic(&mut rng, 3, answer);
+                assert_eq!(eval_exact(&expr).ok(), Some(ExactVal::Rational(answer)));
+            }
+        }
+
+        let answer = Rational::int(i64::MAX);
+        let choices = gen_choices(&mut rng, answer, 3);
+        assert_eq!(choices.len(), 3);
+        assert!(choices.iter().all(|&choice| choice < answer));
+
+        let solution = HashMap::from([('x', answer), ('y', Rational::int(1))]);
+        let choices = gen_system_choices(&mut rng, &solution, 3);
+        assert_eq!(choices.len(), 3);
+    }
+
+    #[test]
+    fn exact_trig_choices_are_wrong_answers() {
+        for seed in 0..50 {
+            let mut rng = seeded_rng(seed);
+            let (question, answer) = gen_exact_trig(&mut rng);
+            assert_eq!(eval_exact(&question).ok(), Some(answer));
+
+            let choices = gen_exact_trig_choices(&mut rng, &question, 3);
+            assert_eq!(choices.len(), 3);
+            assert!(!choices.contains(&answer), "{}", question);
+            for (i, choice) in choices.iter().enumerate() {
+                assert!(!choices[i + 1..].contains(choice), "{}", question);
+            }
+        }
+    }
+
+    #[test]
+    fn exact_trig_choices_prefer_common_mistakes() {
+        let question = parse_expr("sin(pi/3)").unwrap();
+        let choices = gen_exact_trig_choices(&mut seeded_rng(0), &question, 3);
+        let surd = |numerator: i64, radicand: u64| {
+            ExactVal::surd(Rational::new(numerator, 2), radicand, 2).unwrap()
+        };
+        // the wrong sign, the value of cos and the wrong sign of that
+        for mistake in [surd(-1, 3), surd(1, 1), surd(-1, 1)] {
+            assert!(choices.contains(&mistake), "{:?}", choices);
+        }
+    }
+
+    #[test]
+    fn generated_systems_have_their_integer_solution() {
+        for seed in 0..30 {
+            let mut rng = seeded_rng(seed);
+            let var_count = 2 + seed as usize % 2;
+            let (system, solution) = gen_system(&mut rng, var_count);
+            assert_eq!(system.equations.len(), var_count);
+            assert!(solution.values().all(|value| value.denominator == 1));
+            assert_eq!(
+                solve_system(&system).ok(),
+                Some(solution.clone()),
+                "{}",
+                system
+            );
+
+            let choices = gen_system_choices(&mut rng, &solution, 3);
+            assert_eq!(choices.len(), 3);
+            for choice in &choices {
+                let changed = solution
+                    .iter()
+                    .filter(|(var, value)| choice[var] != **value);
+                assert_eq!(changed.count(), 1);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "not 0")]
+    fn systems_need_an_unknown() {
+        gen_system(&mut seeded_rng(0), 0);
+    }
+
+    #[test]
+    #[should_panic(expected = "not 5")]
+    fn systems_have_at_most_four_unknowns() {
+        gen_system(&mut seeded_rng(0), 5);
+    }
+}
@@ -0,0 +1,282 @@
+use std::cmp::Ordering;
+
+use crate::{
+    solve::{difference, polynomial_roots, single_unknown, Root, SolveErr},
+    Comparison, Rational, Relation,
+};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Endpoint {
+    pub value: Root,
+    pub closed: bool,
+}
+
+/// A connected set of reals, unbounded on any side without an endpoint.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Range {
+    pub lower: Option<Endpoint>,
+    pub upper: Option<Endpoint>,
+}
+
+/// The values of `var` which satisfy an inequality, as disjoint ranges in ascending order.
+#[derive(Clone, PartialEq, Debug)]
+pub struct RangeSet {
+    pub var: char,
+    pub ranges: Vec<Range>,
+}
+
+impl Relation {
+    /// The relation after multiplying or dividing both sides by a negative.
+    pub fn flipped(&self) -> Relation {
+        match self {
+            Relation::Less => Relation::Greater,
+            Relation::LessEqual => Relation::GreaterEqual,
+            Relation::Greater => Relation::Less,
+            Relation::GreaterEqual => Relation::LessEqual,
+            relation => *relation,
+        }
+    }
+
+    /// Whether `a relation b` is true when `a` compares to `b` as `ordering`.
+    pub fn holds(&self, ordering: Ordering) -> bool {
+        match self {
+            Relation::Equal => ordering.is_eq(),
+            Relation::NotEqual => ordering.is_ne(),
+            Relation::Less => ordering.is_lt(),
+            Relation::LessEqual => ordering.is_le(),
+            Relation::Greater => ordering.is_gt(),
+            Relation::GreaterEqual => ordering.is_ge(),
+        }
+    }
+}
+
+impl Range {
+    pub fn is_point(&self) -> bool {
+        matches!((&self.lower, &self.upper), (Some(lower), Some(upper)) if lower == upper)
+    }
+
+    pub fn contains(&self, x: f64) -> bool {
+        let above_lower = match &self.lower {
+            Some(Endpoint { value, closed }) => {
+                x > value.value() || (*closed && x == value.value())
+            }
+            None => true,
+        };
+        let below_upper = match &self.upper {
+            Some(Endpoint { value, closed }) => {
+                x < value.value() || (*closed && x == value.value())
+            }
+            None => true,
+        };
+        above_lower && below_upper
+    }
+}
+
+impl RangeSet {
+    pub fn contains(&self, x: f64) -> bool {
+        self.ranges.iter().any(|range| range.contains(x))
+    }
+}
+
+// `included` alternates between the gaps around the roots and the roots themselves, starting and
+// ending with the unbounded gaps, and each run of included pieces becomes one range
+fn ranges_from_pieces(var: char, roots: &[Root], included: &[bool]) -> RangeSet {
+    let lower = |piece: usize| match piece % 2 {
+        0 if piece == 0 => None,
+        0 => Some(Endpoint {
+            value: roots[piece / 2 - 1].clone(),
+            closed: false,
+        }),
+        _ => Some(Endpoint {
+            value: roots[piece / 2].clone(),
+            closed: true,
+        }),
+    };
+    let upper = |piece: usize| match piece % 2 {
+        0 if piece / 2 == roots.len() => None,
+        0 => Some(Endpoint {
+            value: roots[piece / 2].clone(),
+            closed: false,
+        }),
+        _ => Some(Endpoint {
+            value: roots[piece / 2].clone(),
+            closed: true,
+        }),
+    };
+
+    let mut ranges = vec![];
+    let mut start = None;
+    for (piece, &is_included) in included.iter().enumerate() {
+        match (start, is_included) {
+            (None, true) => start = Some(piece),
+            (Some(first), false) => {
+                ranges.push(Range {
+                    lower: lower(first),
+                    upper: upper(piece - 1),
+                });
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(first) = start {
+        ranges.push(Range {
+            lower: lower(first),
+            upper: upper(included.len() - 1),
+        });
+    }
+
+    RangeSet { var, ranges }
+}
+
+/// Solves a polynomial inequality in one unknown. Linear inequalities are rearranged to
+/// `x < c`, flipping the relation when dividing by a negative, and anything of a higher degree is
+/// solved by checking the sign between each of its roots.
+pub fn solve_inequality(comparison: &Comparison) -> Result<RangeSet, SolveErr> {
+    let var = single_unknown(&comparison.lhs, &comparison.rhs)?;
+    let polynomial = difference(&comparison.lhs, &comparison.rhs, var)?;
+    let coefficients = &polynomial.coefficients;
+    let relation = comparison.relation;
+
+    match coefficients.len() {
+        // the unknown cancels out, leaving a comparison between constants
+        0 | 1 => {
+            let constant = coefficients.first().copied().unwrap_or(Rational::int(0));
+            let ranges = if relation.holds(constant.cmp(&Rational::int(0))) {
+                vec![Range {
+                    lower: None,
+                    upper: None,
+                }]
+            } else {
+                vec![]
+            };
+            Ok(RangeSet { var, ranges })
+        }
+        2 => {
+            // a x + b ~ 0 becomes x ~ -b / a
+            let (b, a) = (coefficients[0], coefficients[1]);
+            let boundary = b
+                .checked_neg()
+                .and_then(|b| b.checked_div(a))
+                .map_err(SolveErr::FailedToEval)?;
+            let relation = if a.numerator < 0 {
+                relation.flipped()
+            } else {
+                relation
+            };
+
+            let included = [Ordering::Less, Ordering::Equal, Ordering::Greater]
+                .map(|ordering| relation.holds(ordering));
+            Ok(ranges_from_pieces(
+                var,
+                &[Root::Exact(boundary.into())],
+                &included,
+            ))
+        }
+        _ => {
+            let roots = polynomial_roots(polynomial.clone())?;
+            let values: Vec<f64> = roots.iter().map(Root::value).collect();
+
+            let mut included = vec![];
+            for gap in 0..=roots.len() {
+                let sample = match (gap.checked_sub(1).map(|i| values[i]), values.get(gap)) {
+                    (None, None) => 0.0,
+                    (None, Some(upper)) => upper - 1.0,
+                    (Some(lower), None) => lower + 1.0,
+                    (Some(lower), Some(upper)) => (lower + upper) / 2.0,
+                };
+                let sign = polynomial
+                    .value_at(sample)
+                    .partial_cmp(&0.0)
+                    .unwrap_or(Ordering::Equal);
+                included.push(relation.holds(sign));
+
+                if gap < roots.len() {
+                    included.push(relation.holds(Ordering::Equal));
+                }
+            }
+            Ok(ranges_from_pieces(var, &roots, &included))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gen::{gen_inequality, seeded_rng},
+        parse::parse_expr,
+        render::LatexConvertible,
+    };
+
+    fn solve(lhs: &str, relation: Relation, rhs: &str) -> RangeSet {
+        let comparison = Comparison {
+            lhs: parse_expr(lhs).unwrap(),
+            relation,
+            rhs: parse_expr(rhs).unwrap(),
+        };
+        solve_inequality(&comparison).unwrap()
+    }
+
+    #[test]
+    fn dividing_by_a_negative_flips_the_relation() {
+        let solution = solve("-2*x", Relation::Less, "4");
+        assert_eq!(solution.to_string(), "(-2, ∞)");
+        assert_eq!(solution.to_latex(), "\\left(-2, \\infty\\right)");
+        assert_eq!(solution.to_set_latex(), "\\left\\{x \\mid x > -2\\right\\}");
+
+        assert_eq!(
+            solve("3*x + 1", Relation::GreaterEqual, "7").to_string(),
+            "[2, ∞)"
+        );
+        assert_eq!(
+            solve("x", Relation::NotEqual, "3").to_string(),
+            "(-∞, 3) ∪ (3, ∞)"
+        );
+    }
+
+    #[test]
+    fn quadratics_are_solved_by_their_signs_between_roots() {
+        let outside = solve("x^2 - 5*x + 6", Relation::Greater, "0");
+        assert_eq!(outside.to_string(), "(-∞, 2) ∪ (3, ∞)");
+        assert_eq!(
+            outside.to_latex(),
+            "\\left(-\\infty, 2\\right) \\cup \\left(3, \\infty\\right)"
+        );
+        assert!(outside.contains(1.0) && !outside.contains(2.0) && !outside.contains(2.5));
+
+        let between = solve("x^2 - 5*x + 6", Relation::LessEqual, "0");
+        assert_eq!(between.to_string(), "[2, 3]");
+        assert_eq!(
+            between.to_set_latex(),
+            "\\left\\{x \\mid 2 \\leq x \\leq 3\\right\\}"
+        );
+
+        let surds = solve("x^2", Relation::Less, "2");
+        assert!(surds.contains(1.41) && !surds.contains(1.42) && surds.contains(-1.41));
+    }
+
+    #[test]
+    fn inequalities_can_hold_nowhere_everywhere_or_at_a_point() {
+        let empty = solve("x^2 + 1", Relation::Less, "0");
+        assert_eq!(empty.to_latex(), "\\varnothing");
+        assert_eq!(
+            solve("x + 1", Relation::Greater, "x").to_string(),
+            "(-∞, ∞)"
+        );
+        assert_eq!(solve("x^2", Relation::LessEqual, "0").to_string(), "{0}");
+    }
+
+    #[test]
+    fn generated_inequalities_have_their_solution() {
+        for seed in 0..50 {
+            let (comparison, solution) = gen_inequality(&mut seeded_rng(seed), 1);
+            assert_eq!(
+                solve_inequality(&comparison).ok(),
+                Some(solution),
+                "{}",
+                comparison
+            );
+        }
+    }
+}
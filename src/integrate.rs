@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+
+use crate::{
+    canonical::same,
+    check::{equivalent, Domain},
+    derive::derive,
+    eval::{eval_exact, eval_f64, EvalErr},
+    interval::{eval_interval, Interval},
+    simplify::simplify,
+    Constant, ExactVal, Expr, Func, Op, Pair, Rational,
+};
+
+#[derive(Debug)]
+pub enum IntegrateErr {
+    Unsupported(Expr),
+    Discontinuous,
+    FailedToEval(EvalErr),
+}
+
+/// `∫ integrand d(var)`, between `bounds` when it's a definite integral.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Integral {
+    pub integrand: Expr,
+    pub var: char,
+    pub bounds: Option<(Expr, Expr)>,
+}
+
+fn pair(l: Expr, op: Op, r: Expr) -> Expr {
+    Pair::new(l, op, r).into()
+}
+
+// the constant `a` when `expr` is `ax + b`
+fn linear_coefficient(expr: &Expr, var: char) -> Option<Expr> {
+    let coefficient = simplify(&derive(expr, var));
+    let is_zero = matches!(
+        eval_exact(&coefficient),
+        Ok(ExactVal::Rational(rational)) if rational.numerator == 0
+    );
+
+    if coefficient.unknown_count_of(var) == 0 && !is_zero {
+        Some(coefficient)
+    } else {
+        None
+    }
+}
+
+// divides out a constant factor, as a coefficient when it's rational so that it can be simplified
+fn divide(expr: Expr, divisor: Expr) -> Expr {
+    match eval_exact(&divisor) {
+        Ok(ExactVal::Rational(rational)) if rational == Rational::int(1) => expr,
+        Ok(ExactVal::Rational(rational)) => match Rational::int(1).checked_div(rational) {
+            Ok(reciprocal) => pair(reciprocal.into(), Op::Mul, expr),
+            Err(_) => pair(expr, Op::Div, divisor),
+        },
+        _ => pair(expr, Op::Div, divisor),
+    }
+}
+
+// the value of a ratio like `2x / (2x)` which simplifying doesn't cancel, if it's the same
+// everywhere it's defined
+fn constant_ratio(ratio: &Expr, var: char) -> Option<Expr> {
+    if let Expr::Pair(box Pair {
+        left,
+        op: Op::Div,
+        right,
+    }) = ratio
+    {
+        if same(left, right) {
+            return Some(Rational::int(1).into());
+        }
+    }
+
+    let value: Expr = (1..=5)
+        .find_map(|x| eval_exact(&ratio.substitute(var, &Rational::int(x).into())).ok())?
+        .into();
+    equivalent(ratio, &value, &Domain::default())
+        .equivalent
+        .then_some(value)
+}
+
+fn ln_abs(expr: Expr) -> Expr {
+    Expr::Func(Func::Ln, Box::new(Expr::Func(Func::Abs, Box::new(expr))))
+}
+
+fn integrate_func(func: Func, inner: &Expr, var: char) -> Result<Expr, IntegrateErr> {
+    let unsupported = || IntegrateErr::Unsupported(Expr::Func(func, Box::new(inner.to_owned())));
+    let coefficient = linear_coefficient(inner, var).ok_or_else(unsupported)?;
+    let apply = |func| Expr::Func(func, Box::new(inner.to_owned()));
+
+    let antiderivative = match func {
+        Func::Sine => Expr::Negative(Box::new(apply(Func::Cosine))),
+        Func::Cosine => apply(Func::Sine),
+        Func::Tan => Expr::Negative(Box::new(ln_abs(apply(Func::Cosine)))),
+        Func::Exp => apply(Func::Exp),
+        Func::Ln => pair(
+            pair(inner.to_owned(), Op::Mul, apply(Func::Ln)),
+            Op::Sub,
+            inner.to_owned(),
+        ),
+        Func::Sqrt => pair(
+            pair(Rational::new(2, 3).into(), Op::Mul, inner.to_owned()),
+            Op::Mul,
+            apply(Func::Sqrt),
+        ),
+        _ => return Err(unsupported()),
+    };
+
+    // undo the chain rule for the linear inner function
+    Ok(divide(antiderivative, coefficient))
+}
+
+fn integrate_pow(base: &Expr, exponent: &Expr, var: char) -> Result<Expr, IntegrateErr> {
+    let unsupported =
+        || IntegrateErr::Unsupported(pair(base.to_owned(), Op::Pow, exponent.to_owned()));
+
+    if exponent.unknown_count_of(var) == 0 {
+        let coefficient = linear_coefficient(base, var).ok_or_else(unsupported)?;
+        let power = simplify(&pair(exponent.to_owned(), Op::Add, Rational::int(1).into()));
+        if matches!(eval_exact(&power), Ok(ExactVal::Rational(rational)) if rational.numerator == 0)
+        {
+            return Ok(divide(ln_abs(base.to_owned()), coefficient));
+        }
+
+        Ok(divide(
+            pair(base.to_owned(), Op::Pow, power.clone()),
+            simplify(&pair(coefficient, Op::Mul, power)),
+        ))
+    } else if base.unknown_count_of(var) == 0 {
+        let coefficient = linear_coefficient(exponent, var).ok_or_else(unsupported)?;
+        let ln_base = match base {
+            Expr::Constant(Constant::E) => Rational::int(1).into(),
+            base => Expr::Func(Func::Ln, Box::new(base.to_owned())),
+        };
+
+        Ok(divide(
+            pair(base.to_owned(), Op::Pow, exponent.to_owned()),
+            simplify(&pair(coefficient, Op::Mul, ln_base)),
+        ))
+    } else {
+        Err(unsupported())
+    }
+}
+
+fn integrate_expr(expr: &Expr, var: char) -> Result<Expr, IntegrateErr> {
+    if expr.unknown_count_of(var) == 0 {
+        return Ok(pair(expr.to_owned(), Op::Mul, Expr::Variable(var)));
+    }
+
+    let answer = match expr {
+        Expr::Rational(_) | Expr::Constant(_) => unreachable!("constants are handled above"),
+        Expr::Variable(_) => divide(
+            pair(Expr::Variable(var), Op::Pow, Rational::int(2).into()),
+            Rational::int(2).into(),
+        ),
+        Expr::Negative(inner) => Expr::Negative(Box::new(integrate_expr(inner, var)?)),
+        Expr::Pair(pair_) => match pair_.op {
+            Op::Add | Op::Sub => pair(
+                integrate_expr(&pair_.left, var)?,
+                pair_.op,
+                integrate_expr(&pair_.right, var)?,
+            ),
+            Op::Mul if pair_.left.unknown_count_of(var) == 0 => pair(
+                pair_.left.clone(),
+                Op::Mul,
+                integrate_expr(&pair_.right, var)?,
+            ),
+            Op::Mul if pair_.right.unknown_count_of(var) == 0 => pair(
+                integrate_expr(&pair_.left, var)?,
+                Op::Mul,
+                pair_.right.clone(),
+            ),
+            Op::Div if pair_.right.unknown_count_of(var) == 0 => pair(
+                integrate_expr(&pair_.left, var)?,
+                Op::Div,
+                pair_.right.clone(),
+            ),
+            Op::Div => match &pair_.right {
+                // k / u^n is integrated as k u^-n
+                Expr::Pair(box Pair {
+                    left: base,
+                    op: Op::Pow,
+                    right: exponent,
+                }) if pair_.left.unknown_count_of(var) == 0
+                    && exponent.unknown_count_of(var) == 0 =>
+                {
+                    let exponent = Expr::Negative(Box::new(exponent.to_owned()));
+                    pair(
+                        pair_.left.clone(),
+                        Op::Mul,
+                        integrate_pow(base, &exponent, var)?,
+                    )
+                }
+                denominator => {
+                    // k f'(x) / f(x) integrates to k ln|f(x)|
+                    let mut ratio =
+                        simplify(&pair(pair_.left.clone(), Op::Div, derive(denominator, var)));
+                    if ratio.unknown_count_of(var) != 0 {
+                        ratio = constant_ratio(&ratio, var)
+                            .ok_or_else(|| IntegrateErr::Unsupported(expr.to_owned()))?;
+                    }
+                    pair(ratio, Op::Mul, ln_abs(denominator.to_owned()))
+                }
+            },
+            Op::Pow => integrate_pow(&pair_.left, &pair_.right, var)?,
+            Op::Mul => return Err(IntegrateErr::Unsupported(expr.to_owned())),
+        },
+        Expr::Func(func, inner) => integrate_func(*func, inner, var)?,
+        // integrating undoes the derivative, up to a constant
+        Expr::Derivative(inner, wrt) if *wrt == var => inner.as_ref().to_owned(),
+        Expr::Derivative(inner, wrt) => integrate_expr(&derive(inner, *wrt), var)?,
+    };
+
+    Ok(answer)
+}
+
+/// An antiderivative of `expr` without the constant of integration.
+pub fn integrate(expr: &Expr, var: char) -> Result<Expr, IntegrateErr> {
+    Ok(simplify(&integrate_expr(&simplify(expr), var)?))
+}
+
+/// The indefinite integral of `expr`, with `C` as the constant of integration.
+pub fn integrate_indefinite(expr: &Expr, var: char) -> Result<Expr, IntegrateErr> {
+    Ok(pair(integrate(expr, var)?, Op::Add, Expr::Variable('C')))
+}
+
+/// The exact value of a definite integral, which may still contain functions like `ln(2)`.
+pub fn integrate_definite(
+    expr: &Expr,
+    var: char,
+    lower: &Expr,
+    upper: &Expr,
+) -> Result<Expr, IntegrateErr> {
+    // the fundamental theorem only applies when the integrand is defined across the whole range.
+    // intervals are conservative, so this can refuse some integrals which are actually fine
+    let no_vars = HashMap::new();
+    let lower_val = eval_f64(lower, &no_vars).map_err(IntegrateErr::FailedToEval)?;
+    let upper_val = eval_f64(upper, &no_vars).map_err(IntegrateErr::FailedToEval)?;
+    let range = HashMap::from([(var, Interval::new(lower_val, upper_val))]);
+    if let Err(EvalErr::DivisionByZero | EvalErr::NonReal) = eval_interval(expr, &range) {
+        return Err(IntegrateErr::Discontinuous);
+    }
+
+    let antiderivative = integrate(expr, var)?;
+    Ok(simplify(&pair(
+        antiderivative.substitute(var, upper),
+        Op::Sub,
+        antiderivative.substitute(var, lower),
+    )))
+}
+
+impl Integral {
+    pub fn indefinite(integrand: Expr, var: char) -> Integral {
+        Integral {
+            integrand,
+            var,
+            bounds: None,
+        }
+    }
+
+    pub fn definite(integrand: Expr, var: char, lower: Expr, upper: Expr) -> Integral {
+        Integral {
+            integrand,
+            var,
+            bounds: Some((lower, upper)),
+        }
+    }
+
+    /// The exact value of a definite integral, or the indefinite integral plus `C`.
+    pub fn evaluate(&self) -> Result<Expr, IntegrateErr> {
+        match &self.bounds {
+            Some((lower, upper)) => integrate_definite(&self.integrand, self.var, lower, upper),
+            None => integrate_indefinite(&self.integrand, self.var),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        gen::{gen_integral, seeded_rng},
+        parse::parse_expr,
+    };
+
+    fn assert_integral(integrand: &str, expected: &str) {
+        let antiderivative = integrate(&parse_expr(integrand).unwrap(), 'x').unwrap();
+        let expected = parse_expr(expected).unwrap();
+        assert!(
+            equivalent(&antiderivative, &expected, &Domain::default()).equivalent,
+            "∫ {} dx gave {}",
+            integrand,
+            antiderivative
+        );
+    }
+
+    fn definite(integrand: &str, lower: &str, upper: &str) -> Result<Expr, IntegrateErr> {
+        let parse = |input| parse_expr(input).unwrap();
+        integrate_definite(&parse(integrand), 'x', &parse(lower), &parse(upper))
+    }
+
+    #[test]
+    fn integrates_polynomials_and_functions() {
+        assert_integral("3*x^2 - 4*x + 1", "x^3 - 2*x^2 + x");
+        assert_integral("1/x^2", "-1/x");
+        assert_integral("sin(3*x + 1)", "-cos(3*x + 1)/3");
+        assert_integral("cos(x/2)", "2*sin(x/2)");
+        assert_integral("e^(2*x)", "e^(2*x)/2");
+        assert_integral("(2*x + 1)^3", "(2*x + 1)^4/8");
+    }
+
+    #[test]
+    fn integrates_derivatives_over_functions_to_logarithms() {
+        assert_integral("1/x", "ln(abs(x))");
+        assert_integral("1/(2*x + 1)", "ln(abs(2*x + 1))/2");
+        assert_integral("2*x/(x^2 + 1)", "ln(abs(x^2 + 1))");
+        assert_integral("3*x/(x^2 + 1)", "3*ln(abs(x^2 + 1))/2");
+        assert_integral("(2*x + 3)/(x^2 + 3*x)", "ln(abs(x^2 + 3*x))");
+        assert_integral("cos(x)/sin(x)", "ln(abs(sin(x)))");
+        assert!(matches!(
+            integrate(&parse_expr("x/(x^3 + 1)").unwrap(), 'x'),
+            Err(IntegrateErr::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn indefinite_integrals_have_a_constant() {
+        let integral = integrate_indefinite(&parse_expr("2*x").unwrap(), 'x').unwrap();
+        assert_eq!(integral, parse_expr("x^2 + C").unwrap());
+    }
+
+    #[test]
+    fn definite_integrals_are_exact() {
+        assert_eq!(
+            definite("x^2", "0", "1").ok(),
+            Some(Rational::new(1, 3).into())
+        );
+        assert_eq!(
+            definite("sin(x)", "0", "pi").ok(),
+            Some(Rational::int(2).into())
+        );
+        assert_eq!(
+            definite("1/x", "1", "e").ok(),
+            Some(Rational::int(1).into())
+        );
+    }
+
+    #[test]
+    fn definite_integrals_over_discontinuities_are_errors() {
+        assert!(matches!(
+            definite("1/x", "-1", "1"),
+            Err(IntegrateErr::Discontinuous)
+        ));
+        assert!(matches!(
+            definite("sqrt(x)", "-1", "1"),
+            Err(IntegrateErr::Discontinuous)
+        ));
+        assert!(matches!(
+            definite("1/x", "1", "1/0"),
+            Err(IntegrateErr::FailedToEval(EvalErr::DivisionByZero))
+        ));
+    }
+
+    #[test]
+    fn generated_integrals_have_the_integrand_as_the_derivative_of_the_answer() {
+        for seed in 0..50 {
+            let (integral, answer) = gen_integral(&mut seeded_rng(seed), 2);
+            let derivative = derive(&answer, 'x');
+            assert!(
+                equivalent(&derivative, &integral.integrand, &Domain::default()).equivalent,
+                "{}",
+                integral.integrand
+            );
+        }
+    }
+}
@@ -0,0 +1,336 @@
+use std::{
+    collections::HashMap,
+    f64::consts::{FRAC_PI_2, PI, TAU},
+};
+
+use crate::{
+    derive::derive,
+    eval::{eval, pow_f64, EvalErr},
+    Expr, Func, Op, Rational,
+};
+
+// slack when deciding whether a turning point of a periodic function lies inside an interval,
+// erring towards the wider result
+const ANGLE_TOLERANCE: f64 = 1e-12;
+
+/// A closed range of reals which is guaranteed to contain the exact value it approximates.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Interval {
+    pub lower: f64,
+    pub upper: f64,
+}
+
+impl Interval {
+    pub fn new(a: f64, b: f64) -> Interval {
+        Interval {
+            lower: a.min(b),
+            upper: a.max(b),
+        }
+    }
+
+    pub fn point(x: f64) -> Interval {
+        Interval::new(x, x)
+    }
+
+    pub fn width(&self) -> f64 {
+        self.upper - self.lower
+    }
+
+    pub fn contains(&self, x: f64) -> bool {
+        self.lower <= x && x <= self.upper
+    }
+
+    // every operation rounds to nearest, so pushing each bound out by one step keeps the exact
+    // value inside
+    fn widened(self) -> Interval {
+        Interval {
+            lower: self.lower.next_down(),
+            upper: self.upper.next_up(),
+        }
+    }
+
+    fn checked(self) -> Result<Interval, EvalErr> {
+        if self.lower.is_nan() || self.upper.is_nan() {
+            Err(EvalErr::NonReal)
+        } else if self.lower.is_infinite() || self.upper.is_infinite() {
+            Err(EvalErr::Overflow)
+        } else {
+            Ok(self)
+        }
+    }
+
+    fn hull(values: [f64; 4]) -> Interval {
+        let lower = values.iter().copied().fold(f64::INFINITY, f64::min);
+        let upper = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        Interval::new(lower, upper)
+    }
+
+    pub fn checked_add(self, rhs: Interval) -> Result<Interval, EvalErr> {
+        Interval::new(self.lower + rhs.lower, self.upper + rhs.upper)
+            .widened()
+            .checked()
+    }
+
+    pub fn checked_sub(self, rhs: Interval) -> Result<Interval, EvalErr> {
+        self.checked_add(-rhs)
+    }
+
+    pub fn checked_mul(self, rhs: Interval) -> Result<Interval, EvalErr> {
+        Interval::hull([
+            self.lower * rhs.lower,
+            self.lower * rhs.upper,
+            self.upper * rhs.lower,
+            self.upper * rhs.upper,
+        ])
+        .widened()
+        .checked()
+    }
+
+    pub fn recip(self) -> Result<Interval, EvalErr> {
+        if self.contains(0.0) {
+            return Err(EvalErr::DivisionByZero);
+        }
+
+        Interval::new(1.0 / self.lower, 1.0 / self.upper)
+            .widened()
+            .checked()
+    }
+
+    pub fn checked_div(self, rhs: Interval) -> Result<Interval, EvalErr> {
+        self.checked_mul(rhs.recip()?)
+    }
+
+    fn powi(self, exponent: i64) -> Result<Interval, EvalErr> {
+        if exponent < 0 {
+            return self.powi(-exponent)?.recip();
+        }
+
+        let exponent = i32::try_from(exponent).map_err(|_| EvalErr::Overflow)?;
+        let lower = self.lower.powi(exponent);
+        let upper = self.upper.powi(exponent);
+        let mut interval = if exponent % 2 == 1 {
+            Interval::new(lower, upper)
+        } else if self.contains(0.0) {
+            Interval::new(0.0, lower.max(upper))
+        } else {
+            Interval::new(lower, upper)
+        }
+        .widened();
+
+        // even powers are never negative, however the bounds were rounded
+        if exponent % 2 == 0 {
+            interval.lower = interval.lower.max(0.0);
+        }
+        interval.checked()
+    }
+
+    fn root(self, index: u64) -> Result<Interval, EvalErr> {
+        if index.is_multiple_of(2) && self.lower < 0.0 {
+            return Err(EvalErr::NonReal);
+        }
+
+        let exponent = Rational::new(1, index);
+        let power = 1.0 / index as f64;
+        Interval::new(
+            pow_f64(self.lower, power, Some(exponent)),
+            pow_f64(self.upper, power, Some(exponent)),
+        )
+        .widened()
+        .checked()
+    }
+
+    /// Raises to a power which is only known approximately, so the base must be positive.
+    pub fn pow(self, exponent: Interval) -> Result<Interval, EvalErr> {
+        if self.lower <= 0.0 {
+            return Err(EvalErr::NonReal);
+        }
+
+        Interval::hull([
+            self.lower.powf(exponent.lower),
+            self.lower.powf(exponent.upper),
+            self.upper.powf(exponent.lower),
+            self.upper.powf(exponent.upper),
+        ])
+        .widened()
+        .checked()
+    }
+
+    pub fn pow_rational(self, exponent: Rational) -> Result<Interval, EvalErr> {
+        if exponent.denominator == 1 {
+            self.powi(exponent.numerator)
+        } else {
+            self.root(exponent.denominator)?.powi(exponent.numerator)
+        }
+    }
+
+    // bounds a function with period 2π whose maximum is at `peak` and minimum at `trough`
+    fn periodic(self, func: fn(f64) -> f64, peak: f64, trough: f64) -> Interval {
+        if self.width() >= TAU {
+            return Interval::new(-1.0, 1.0);
+        }
+
+        let contains_angle = |angle: f64| {
+            let turns = ((self.lower - angle) / TAU).ceil();
+            [turns - 1.0, turns].iter().any(|turns| {
+                let angle = angle + turns * TAU;
+                self.lower - ANGLE_TOLERANCE <= angle && angle <= self.upper + ANGLE_TOLERANCE
+            })
+        };
+
+        let mut interval = Interval::new(func(self.lower), func(self.upper)).widened();
+        if contains_angle(peak) {
+            interval.upper = 1.0;
+        }
+        if contains_angle(trough) {
+            interval.lower = -1.0;
+        }
+        Interval::new(interval.lower.max(-1.0), interval.upper.min(1.0))
+    }
+
+    // bounds a function which is monotonic across the whole interval, in either direction
+    fn monotonic(self, func: &Func) -> Interval {
+        Interval::new(func.apply(self.lower), func.apply(self.upper)).widened()
+    }
+
+    pub fn apply(self, func: &Func) -> Result<Interval, EvalErr> {
+        let interval = match func {
+            Func::Sine => self.periodic(f64::sin, FRAC_PI_2, -FRAC_PI_2),
+            Func::Cosine => self.periodic(f64::cos, 0.0, PI),
+            Func::Tan => {
+                // tan is only increasing between its asymptotes at odd multiples of π/2
+                if self.width() >= PI
+                    || ((self.lower - FRAC_PI_2) / PI).ceil()
+                        <= ((self.upper - FRAC_PI_2) / PI).floor()
+                {
+                    return Err(EvalErr::DivisionByZero);
+                }
+                self.monotonic(func)
+            }
+            Func::Ln | Func::Log(_) if self.lower <= 0.0 => return Err(EvalErr::NonReal),
+            Func::Sqrt if self.lower < 0.0 => return Err(EvalErr::NonReal),
+            Func::Arcsin | Func::Arccos if self.lower < -1.0 || self.upper > 1.0 => {
+                return Err(EvalErr::NonReal)
+            }
+            Func::Exp
+            | Func::Ln
+            | Func::Log(_)
+            | Func::Sqrt
+            | Func::Arcsin
+            | Func::Arccos
+            | Func::Arctan => self.monotonic(func),
+            Func::Abs if self.contains(0.0) => {
+                Interval::new(0.0, self.lower.abs().max(self.upper.abs()))
+            }
+            Func::Abs => Interval::new(self.lower.abs(), self.upper.abs()),
+        };
+
+        interval.checked()
+    }
+}
+
+impl std::ops::Neg for Interval {
+    type Output = Interval;
+
+    fn neg(self) -> Self::Output {
+        Interval::new(-self.upper, -self.lower)
+    }
+}
+
+impl From<Rational> for Interval {
+    fn from(rational: Rational) -> Self {
+        Interval::point(rational.numerator as f64 / rational.denominator as f64).widened()
+    }
+}
+
+/// Bounds the value of `expr` when each variable can be anywhere in its interval.
+pub fn eval_interval(expr: &Expr, vars: &HashMap<char, Interval>) -> Result<Interval, EvalErr> {
+    let answer = match expr {
+        Expr::Rational(rational) if rational.denominator == 0 => {
+            return Err(EvalErr::DivisionByZero)
+        }
+        Expr::Rational(rational) => Interval::from(*rational),
+        Expr::Pair(pair) => {
+            let lval = eval_interval(&pair.left, vars)?;
+            let rval = eval_interval(&pair.right, vars)?;
+
+            match pair.op {
+                Op::Add => lval.checked_add(rval)?,
+                Op::Sub => lval.checked_sub(rval)?,
+                Op::Mul => lval.checked_mul(rval)?,
+                Op::Div => lval.checked_div(rval)?,
+                // constant exponents are handled exactly so that negative bases still work
+                Op::Pow => match eval(&pair.right) {
+                    Ok(exponent) => lval.pow_rational(exponent)?,
+                    Err(_) => lval.pow(rval)?,
+                },
+            }
+        }
+        Expr::Negative(expr) => -eval_interval(expr, vars)?,
+        Expr::Constant(constant) => Interval::point(constant.value()).widened(),
+        Expr::Variable(unknown) => match vars.get(unknown) {
+            Some(value) => *value,
+            None => return Err(EvalErr::EncounteredUnknown(unknown.to_owned())),
+        },
+        Expr::Derivative(expr, var) => eval_interval(&derive(expr, *var), vars)?,
+        Expr::Func(func, inner) => eval_interval(inner, vars)?.apply(func)?,
+    };
+
+    Ok(answer)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{eval::eval_f64, parse::parse_expr};
+
+    #[test]
+    fn bounds_contain_the_float_value() {
+        let exprs = [
+            "sin(x) + cos(x)",
+            "tan(x/3)",
+            "exp(x) - ln(x + 2)",
+            "log(x + 2)*sqrt(x + 1)",
+            "abs(x - 3)",
+            "arcsin(x/2) + arccos(x/2) + arctan(x)",
+            "x^3/(x + 5)",
+            "pi*x^2 + e",
+        ];
+        for expr in exprs {
+            let expr = parse_expr(expr).unwrap();
+            for x in [-0.5, 0.25, 1.0, 1.5] {
+                let value = eval_f64(&expr, &HashMap::from([('x', x)])).unwrap();
+                let bounds =
+                    eval_interval(&expr, &HashMap::from([('x', Interval::point(x))])).unwrap();
+                assert!(bounds.contains(value), "{} at {}", expr, x);
+                assert!(bounds.width() < 1e-9, "{} at {}", expr, x);
+            }
+        }
+    }
+
+    #[test]
+    fn bounds_include_turning_points() {
+        let sin = parse_expr("sin(x)").unwrap();
+        let bounds = eval_interval(&sin, &HashMap::from([('x', Interval::new(0.0, PI))])).unwrap();
+        assert!(bounds.contains(1.0));
+        assert!(bounds.contains(0.0));
+
+        let square = parse_expr("x^2").unwrap();
+        let bounds =
+            eval_interval(&square, &HashMap::from([('x', Interval::new(-1.0, 2.0))])).unwrap();
+        assert!(bounds.contains(0.0) && bounds.contains(4.0));
+        assert!(!bounds.contains(-0.1));
+    }
+
+    #[test]
+    fn dividing_by_an_interval_containing_zero_is_an_error() {
+        let expr = parse_expr("1/x").unwrap();
+        assert!(matches!(
+            eval_interval(&expr, &HashMap::from([('x', Interval::new(-1.0, 1.0))])),
+            Err(EvalErr::DivisionByZero)
+        ));
+        assert!(matches!(
+            eval_interval(&expr, &HashMap::new()),
+            Err(EvalErr::EncounteredUnknown('x'))
+        ));
+    }
+}
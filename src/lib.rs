@@ -1,18 +1,34 @@
 #![feature(box_patterns)]
 
-use std::char;
+use std::{
+    char,
+    cmp::Ordering,
+    collections::HashMap,
+    hash::{Hash, Hasher},
+};
 
+use derive::derive;
 use render::LatexConvertible;
 
+#[cfg(feature = "big-rational")]
+pub mod big;
+pub mod canonical;
+pub mod check;
 pub mod derive;
 pub mod eval;
 pub mod fmt;
 pub mod gen;
+pub mod inequality;
+pub mod integrate;
+pub mod interval;
+pub mod parse;
+pub mod polynomial;
 pub mod render;
+pub mod rewrite;
 pub mod simplify;
 pub mod solve;
 
-#[derive(PartialEq, Clone, Copy, Debug)]
+#[derive(PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy, Debug)]
 pub enum Op {
     Add,
     Sub,
@@ -48,7 +64,7 @@ impl Op {
     }
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub struct Pair {
     pub left: Expr,
     pub right: Expr,
@@ -111,38 +127,103 @@ impl Into<Expr> for Pair {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug)]
 pub struct Rational {
     pub numerator: i64,
     pub denominator: u64,
 }
 
+// the fields are public, so comparisons can't assume that the fraction is in lowest terms. a zero
+// denominator would cross-multiply equal to everything, so those fractions are only equal to each
+// other by sign, and come after every defined value
+impl Ord for Rational {
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self.denominator == 0, other.denominator == 0) {
+            (false, false) => {
+                let lcross = self.numerator as i128 * other.denominator as i128;
+                let rcross = other.numerator as i128 * self.denominator as i128;
+                lcross.cmp(&rcross)
+            }
+            (true, true) => self.numerator.signum().cmp(&other.numerator.signum()),
+            (lundefined, rundefined) => lundefined.cmp(&rundefined),
+        }
+    }
+}
+
 impl PartialOrd for Rational {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        let lfloat = self.numerator as f64 / self.denominator as f64;
-        let rfloat = other.numerator as f64 / other.denominator as f64;
-        lfloat.partial_cmp(&rfloat)
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Rational {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Rational {}
+
+impl Hash for Rational {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let simplified = self.simplified();
+        simplified.numerator.hash(state);
+        simplified.denominator.hash(state);
     }
 }
 
+/// `coefficient * radicand^(1/index)`, kept with the largest possible factor moved outside the root.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Surd {
+    pub coefficient: Rational,
+    pub radicand: u64,
+    pub index: u32,
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Constant {
+    Pi,
+    E,
+}
+
+/// `coefficient * constant`, so that values like `3π/4` stay exact.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Multiple {
+    pub coefficient: Rational,
+    pub constant: Constant,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum ExactVal {
     Rational(Rational),
+    Surd(Surd),
+    Multiple(Multiple),
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
 pub enum Func {
     Sine,
     Cosine,
+    Tan,
+    Exp,
+    Ln,
+    Log(u64),
+    Sqrt,
+    Abs,
+    Arcsin,
+    Arccos,
+    Arctan,
 }
 
-#[derive(Clone, PartialEq, Debug)]
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
 pub enum Expr {
     Rational(Rational),
     Pair(Box<Pair>),
     Negative(Box<Expr>),
     Variable(char),
-    Derivative(Box<Expr>),
+    Derivative(Box<Expr>, char),
     Func(Func, Box<Expr>),
+    Constant(Constant),
 }
 
 impl Expr {
@@ -152,17 +233,174 @@ impl Expr {
             Expr::Pair(pair) => pair.op.precedence(),
             Expr::Negative(_) => 1,
             Expr::Variable(_) => 3,
-            Expr::Derivative(_) => 4,
+            Expr::Constant(_) => 3,
+            Expr::Derivative(..) => 4,
             Expr::Func(_, _) => 4,
         }
     }
+
+    pub fn variables(&self) -> Vec<char> {
+        let mut vars = vec![];
+        self.collect_variables(&mut vars);
+        vars
+    }
+
+    fn collect_variables(&self, vars: &mut Vec<char>) {
+        match self {
+            Expr::Rational(_) | Expr::Constant(_) => (),
+            Expr::Pair(pair) => {
+                pair.left.collect_variables(vars);
+                pair.right.collect_variables(vars);
+            }
+            Expr::Variable(var) => {
+                if !vars.contains(var) {
+                    vars.push(*var);
+                }
+            }
+            Expr::Negative(inner) | Expr::Func(_, inner) => inner.collect_variables(vars),
+            Expr::Derivative(inner, var) => {
+                inner.collect_variables(vars);
+                if !vars.contains(var) {
+                    vars.push(*var);
+                }
+            }
+        }
+    }
+
+    pub fn substitute(&self, var: char, replacement: &Expr) -> Expr {
+        match self {
+            Expr::Rational(_) | Expr::Constant(_) => self.to_owned(),
+            Expr::Pair(pair) => Pair::new(
+                pair.left.substitute(var, replacement),
+                pair.op,
+                pair.right.substitute(var, replacement),
+            )
+            .into(),
+            Expr::Variable(unknown) if *unknown == var => replacement.to_owned(),
+            Expr::Variable(_) => self.to_owned(),
+            Expr::Negative(inner) => Expr::Negative(Box::new(inner.substitute(var, replacement))),
+            // the derivative has to be taken before the variable it's with respect to is replaced
+            Expr::Derivative(inner, wrt) if *wrt == var => {
+                derive(inner, *wrt).substitute(var, replacement)
+            }
+            Expr::Derivative(inner, wrt) => {
+                Expr::Derivative(Box::new(inner.substitute(var, replacement)), *wrt)
+            }
+            Expr::Func(func, inner) => {
+                Expr::Func(*func, Box::new(inner.substitute(var, replacement)))
+            }
+        }
+    }
 }
 
+// a total order on the structure of expressions, used to put the operands of commutative operators
+// in a canonical order
+impl Ord for Expr {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let rank = |expr: &Expr| match expr {
+            Expr::Pair(_) => 0,
+            Expr::Func(..) => 1,
+            Expr::Derivative(..) => 2,
+            Expr::Negative(_) => 3,
+            Expr::Variable(_) => 4,
+            Expr::Constant(_) => 5,
+            Expr::Rational(_) => 6,
+        };
+
+        match (self, other) {
+            (Expr::Pair(lpair), Expr::Pair(rpair)) => lpair
+                .op
+                .cmp(&rpair.op)
+                .then_with(|| lpair.left.cmp(&rpair.left))
+                .then_with(|| lpair.right.cmp(&rpair.right)),
+            (Expr::Func(lfunc, linner), Expr::Func(rfunc, rinner)) => {
+                lfunc.cmp(rfunc).then_with(|| linner.cmp(rinner))
+            }
+            (Expr::Derivative(linner, lvar), Expr::Derivative(rinner, rvar)) => {
+                lvar.cmp(rvar).then_with(|| linner.cmp(rinner))
+            }
+            (Expr::Negative(linner), Expr::Negative(rinner)) => linner.cmp(rinner),
+            (Expr::Variable(lvar), Expr::Variable(rvar)) => lvar.cmp(rvar),
+            (Expr::Constant(lconstant), Expr::Constant(rconstant)) => lconstant.cmp(rconstant),
+            (Expr::Rational(lrational), Expr::Rational(rrational)) => lrational.cmp(rrational),
+            _ => rank(self).cmp(&rank(other)),
+        }
+    }
+}
+
+impl PartialOrd for Expr {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
 pub struct Equation {
     pub lhs: Expr,
     pub rhs: Expr,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Relation {
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+}
+
+/// `lhs relation rhs`, generalising `Equation` to inequalities.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Comparison {
+    pub lhs: Expr,
+    pub relation: Relation,
+    pub rhs: Expr,
+}
+
+impl From<Equation> for Comparison {
+    fn from(equation: Equation) -> Self {
+        Comparison {
+            lhs: equation.lhs,
+            relation: Relation::Equal,
+            rhs: equation.rhs,
+        }
+    }
+}
+
+/// Simultaneous equations which share their unknowns.
+#[derive(Clone, PartialEq, Debug)]
+pub struct System {
+    pub equations: Vec<Equation>,
+}
+
+impl System {
+    pub fn variables(&self) -> Vec<char> {
+        let mut vars = vec![];
+        for equation in &self.equations {
+            equation.lhs.collect_variables(&mut vars);
+            equation.rhs.collect_variables(&mut vars);
+        }
+        vars.sort();
+        vars
+    }
+
+    /// `x = 1, y = 2, ...` for each unknown in `solution`, in alphabetical order.
+    pub fn assignments(solution: &HashMap<char, Rational>) -> System {
+        let mut vars: Vec<&char> = solution.keys().collect();
+        vars.sort();
+        System {
+            equations: vars
+                .into_iter()
+                .map(|var| Equation {
+                    lhs: Expr::Variable(*var),
+                    rhs: solution[var].into(),
+                })
+                .collect(),
+        }
+    }
+}
+
 pub struct Answer<T: LatexConvertible> {
     pub option: char,
     pub answer: T,
@@ -177,9 +415,15 @@ impl Rational {
     }
 
     pub fn new(numerator: i64, denominator: u64) -> Self {
-        Self {
+        let rational = Self {
             numerator,
             denominator,
+        };
+
+        if denominator == 0 {
+            rational
+        } else {
+            rational.simplified()
         }
     }
 }
@@ -189,3 +433,140 @@ impl Into<Expr> for Rational {
         Expr::Rational(self)
     }
 }
+
+impl From<Surd> for Expr {
+    fn from(surd: Surd) -> Expr {
+        let root: Expr = Pair::new(
+            Rational::int(surd.radicand as i64).into(),
+            Op::Pow,
+            Rational::new(1, surd.index as u64).into(),
+        )
+        .into();
+
+        let magnitude = Rational::new(
+            surd.coefficient.numerator.abs(),
+            surd.coefficient.denominator,
+        );
+        let positive = if magnitude == Rational::int(1) {
+            root
+        } else {
+            Pair::new(magnitude.into(), Op::Mul, root).into()
+        };
+
+        if surd.coefficient.numerator < 0 {
+            Expr::Negative(Box::new(positive))
+        } else {
+            positive
+        }
+    }
+}
+
+impl From<Multiple> for Expr {
+    fn from(multiple: Multiple) -> Expr {
+        // written as `3π / 4` rather than `3/4 * π`
+        let numerator = multiple.coefficient.numerator.unsigned_abs();
+        let scaled = if numerator == 1 {
+            Expr::Constant(multiple.constant)
+        } else {
+            Pair::new(
+                Rational::int(numerator as i64).into(),
+                Op::Mul,
+                Expr::Constant(multiple.constant),
+            )
+            .into()
+        };
+        let positive = if multiple.coefficient.denominator == 1 {
+            scaled
+        } else {
+            Pair::new(
+                scaled,
+                Op::Div,
+                Rational::int(multiple.coefficient.denominator as i64).into(),
+            )
+            .into()
+        };
+
+        if multiple.coefficient.numerator < 0 {
+            Expr::Negative(Box::new(positive))
+        } else {
+            positive
+        }
+    }
+}
+
+impl From<ExactVal> for Expr {
+    fn from(val: ExactVal) -> Expr {
+        match val {
+            ExactVal::Rational(rational) => rational.into(),
+            ExactVal::Surd(surd) => surd.into(),
+            ExactVal::Multiple(multiple) => multiple.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::hash_map::DefaultHasher;
+
+    use super::*;
+
+    fn hash(rational: Rational) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        rational.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn substitutes_every_occurrence_of_one_variable() {
+        let expr = parse::parse_expr("x*y + x^2").unwrap();
+        let replacement = parse::parse_expr("y + 1").unwrap();
+        let substituted = expr.substitute('x', &replacement);
+        assert_eq!(
+            substituted,
+            parse::parse_expr("(y + 1)*y + (y + 1)^2").unwrap()
+        );
+        assert_eq!(substituted.variables(), vec!['y']);
+        assert_eq!(expr.substitute('z', &replacement), expr);
+    }
+
+    #[test]
+    fn substitutes_into_derivatives_after_taking_them() {
+        let expr = Expr::Derivative(Box::new(parse::parse_expr("x^2").unwrap()), 'x');
+        let substituted = expr.substitute('x', &Rational::int(3).into());
+        assert_eq!(eval::eval(&substituted).ok(), Some(Rational::int(6)));
+    }
+
+    #[test]
+    fn rationals_compare_by_value() {
+        assert_eq!(Rational::new(2, 4), Rational::new(1, 2));
+        assert_eq!(Rational::new(-2, 4), Rational::new(-1, 2));
+        assert_ne!(Rational::new(1, 2), Rational::new(-1, 2));
+        assert!(Rational::new(-1, 2) < Rational::new(-1, 3));
+        assert!(Rational::int(-3) < Rational::new(-5, 2));
+        assert!(Rational::new(-1, 3) < Rational::int(0));
+    }
+
+    #[test]
+    fn equal_rationals_hash_the_same() {
+        let unsimplified = Rational {
+            numerator: 6,
+            denominator: 4,
+        };
+        assert_eq!(unsimplified, Rational::new(3, 2));
+        assert_eq!(hash(unsimplified), hash(Rational::new(3, 2)));
+        assert_eq!(hash(Rational::new(0, 5)), hash(Rational::int(0)));
+    }
+
+    #[test]
+    fn zero_denominators_are_not_equal_to_everything() {
+        let undefined = Rational::new(0, 0);
+        let infinite = Rational::new(3, 0);
+        assert_ne!(undefined, Rational::int(1));
+        assert_ne!(undefined, Rational::int(2));
+        assert_ne!(infinite, Rational::int(3));
+        assert_ne!(undefined, infinite);
+        assert_eq!(infinite, Rational::new(5, 0));
+        assert_eq!(hash(infinite), hash(Rational::new(5, 0)));
+        assert!(Rational::int(i64::MAX) < infinite);
+    }
+}
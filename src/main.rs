@@ -1,19 +1,17 @@
 use maths_problem_gen::{
     gen::{gen_derivable, gen_polynomial},
+    parse::parse_expr,
     simplify::simplify,
     Equation, Func,
 };
 use std::path::Path;
 
-use maths_problem_gen::{
-    derive::derive, gen::gen_backtrack, render::render_to_file, solve::solve, Expr, Op, Pair,
-    Rational,
-};
+use maths_problem_gen::{derive::derive, gen::gen_backtrack, render::render_to_file, solve::solve};
 use tokio;
 
 #[tokio::main]
 async fn main() {
-    // let (equation, answer) = gen_backtrack(2);
+    // let (equation, answer) = gen_backtrack(&mut rand::thread_rng(), 2, false);
     // println!("{}, x = {}", &equation, &answer);
     // let solved_answer = solve(&equation).expect("Should be solvable");
     // assert_eq!(answer, solved_answer);
@@ -28,15 +26,10 @@ async fn main() {
     //     Rational::int(2).into(),
     // ).into();
 
-    let expr = Pair::new(
-        Expr::Variable('x'),
-        Op::Div,
-        Pair::new(Expr::Variable('x'), Op::Pow, Rational::int(3).into()).into(),
-    )
-    .into();
+    let expr = parse_expr("x / x^3").expect("expression should parse");
 
     render_to_file(
-        &simplify(&derive(&expr)),
+        &simplify(&derive(&expr, 'x')),
         &Path::new("out.png"),
         None,
         false,
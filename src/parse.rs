@@ -0,0 +1,696 @@
+use crate::{eval::gcd, Constant, Equation, Expr, Func, Op, Pair, Rational};
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum ParseErr {
+    UnexpectedChar(usize, char),
+    UnexpectedToken(usize, String),
+    UnexpectedEnd(usize),
+    UnclosedBracket(usize),
+    NumberTooLarge(usize),
+    MissingEquals,
+}
+
+impl ParseErr {
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            ParseErr::UnexpectedChar(pos, _)
+            | ParseErr::UnexpectedToken(pos, _)
+            | ParseErr::UnexpectedEnd(pos)
+            | ParseErr::UnclosedBracket(pos)
+            | ParseErr::NumberTooLarge(pos) => Some(*pos),
+            ParseErr::MissingEquals => None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+enum Token {
+    Number(Rational),
+    Variable(char),
+    Constant(Constant),
+    Func(Func),
+    Derivative(char),
+    Op(Op),
+    Open,
+    Close,
+    Equals,
+    Frac,
+    OpenBrace,
+    CloseBrace,
+}
+
+impl Token {
+    fn starts_operand(&self) -> bool {
+        matches!(
+            self,
+            Token::Number(_)
+                | Token::Variable(_)
+                | Token::Constant(_)
+                | Token::Func(_)
+                | Token::Derivative(_)
+                | Token::Open
+                | Token::Frac
+                | Token::OpenBrace
+        )
+    }
+}
+
+fn func_from_name(name: &str) -> Option<Func> {
+    match name {
+        "sin" => Some(Func::Sine),
+        "cos" => Some(Func::Cosine),
+        "tan" => Some(Func::Tan),
+        "exp" => Some(Func::Exp),
+        "ln" => Some(Func::Ln),
+        "log" => Some(Func::Log(10)),
+        "sqrt" => Some(Func::Sqrt),
+        "abs" => Some(Func::Abs),
+        "arcsin" => Some(Func::Arcsin),
+        "arccos" => Some(Func::Arccos),
+        "arctan" => Some(Func::Arctan),
+        _ => None,
+    }
+}
+
+// `e` and `π` are always read as constants rather than variables
+fn lex_letter(c: char) -> Token {
+    match c {
+        'e' => Token::Constant(Constant::E),
+        'π' => Token::Constant(Constant::Pi),
+        c => Token::Variable(c),
+    }
+}
+
+// longer names first, since they're matched as prefixes
+const FUNC_NAMES: [&str; 11] = [
+    "arcsin", "arccos", "arctan", "sin", "cos", "tan", "exp", "ln", "log", "sqrt", "abs",
+];
+
+// reads a function name starting at `start`, including the `_2` or `_{2}` base of a log
+fn lex_func(chars: &[char], start: usize, name: &str) -> Result<(Func, usize), ParseErr> {
+    let func = func_from_name(name).unwrap();
+    let end = start + name.len();
+    if func != Func::Log(10) || chars.get(end) != Some(&'_') {
+        return Ok((func, end));
+    }
+
+    let braced = chars.get(end + 1) == Some(&'{');
+    let base_start = end + 1 + braced as usize;
+    let (base, mut end) = lex_number(chars, base_start)?;
+    if base.denominator != 1 || base.numerator < 2 {
+        return Err(match chars.get(base_start) {
+            Some(c) => ParseErr::UnexpectedChar(base_start, *c),
+            None => ParseErr::UnexpectedEnd(base_start),
+        });
+    }
+    if braced {
+        match chars.get(end) {
+            Some('}') => end += 1,
+            Some(c) => return Err(ParseErr::UnexpectedChar(end, *c)),
+            None => return Err(ParseErr::UnexpectedEnd(end)),
+        }
+    }
+
+    Ok((Func::Log(base.numerator as u64), end))
+}
+
+fn lex_number(chars: &[char], start: usize) -> Result<(Rational, usize), ParseErr> {
+    let mut end = start;
+    let mut numerator: i64 = 0;
+    let mut denominator: u64 = 1;
+    let mut seen_point = false;
+    let mut seen_digit = false;
+
+    while end < chars.len() {
+        let c = chars[end];
+        if c == '.' && !seen_point {
+            seen_point = true;
+        } else if c == '.' {
+            // `1.2.3` isn't a number, rather than the product `1.2 * .3`
+            return Err(ParseErr::UnexpectedChar(end, c));
+        } else if let Some(digit) = c.to_digit(10) {
+            seen_digit = true;
+            numerator = numerator
+                .checked_mul(10)
+                .and_then(|n| n.checked_add(digit as i64))
+                .ok_or(ParseErr::NumberTooLarge(start))?;
+            if seen_point {
+                denominator = denominator
+                    .checked_mul(10)
+                    .ok_or(ParseErr::NumberTooLarge(start))?;
+            }
+        } else {
+            break;
+        }
+        end += 1;
+    }
+    if !seen_digit {
+        return Err(ParseErr::UnexpectedChar(start, '.'));
+    }
+
+    Ok((Rational::new(numerator, denominator), end))
+}
+
+fn lex(input: &str) -> Result<Vec<(usize, Token)>, ParseErr> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let token = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let (rational, end) = lex_number(&chars, i)?;
+                tokens.push((i, Token::Number(rational)));
+                i = end;
+                continue;
+            }
+            c if c.is_alphabetic() => {
+                let rest: String = chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_alphabetic())
+                    .collect();
+                let derivative = match rest.strip_prefix("dd") {
+                    Some(var) if var.len() == 1 && chars.get(i + 3) == Some(&'(') => {
+                        var.chars().next()
+                    }
+                    _ => None,
+                };
+                if let Some(var) = derivative {
+                    tokens.push((i, Token::Derivative(var)));
+                    i += 3;
+                } else if let Some(name) = FUNC_NAMES.iter().find(|name| rest.starts_with(*name)) {
+                    let (func, end) = lex_func(&chars, i, name)?;
+                    tokens.push((i, Token::Func(func)));
+                    i = end;
+                } else if rest.starts_with("pi") {
+                    tokens.push((i, Token::Constant(Constant::Pi)));
+                    i += 2;
+                } else {
+                    tokens.push((i, lex_letter(c)));
+                    i += 1;
+                }
+                continue;
+            }
+            '+' => Token::Op(Op::Add),
+            '-' => Token::Op(Op::Sub),
+            '*' => Token::Op(Op::Mul),
+            '/' => Token::Op(Op::Div),
+            '^' => Token::Op(Op::Pow),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '=' => Token::Equals,
+            c => return Err(ParseErr::UnexpectedChar(i, c)),
+        };
+        tokens.push((i, token));
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+// matches the `{d}{dx}` following `\\frac`
+fn is_latex_derivative(rest: &str) -> bool {
+    let chars: Vec<char> = rest.chars().take(7).collect();
+    chars.len() == 7
+        && chars[..5] == ['{', 'd', '}', '{', 'd']
+        && chars[5].is_alphabetic()
+        && chars[6] == '}'
+}
+
+fn lex_latex(input: &str) -> Result<Vec<(usize, Token)>, ParseErr> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = vec![];
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let token = match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            c if c.is_ascii_digit() || c == '.' => {
+                let (rational, end) = lex_number(&chars, i)?;
+                tokens.push((i, Token::Number(rational)));
+                i = end;
+                continue;
+            }
+            '\\' => {
+                let command: String = chars[(i + 1)..]
+                    .iter()
+                    .take_while(|c| c.is_alphabetic())
+                    .collect();
+                let rest: String = chars[(i + 1 + command.len())..].iter().collect();
+                let (token, len) = match command.as_str() {
+                    "left" | "right" => match rest.chars().next() {
+                        Some('(') if command == "left" => (Some(Token::Open), 1),
+                        Some(')') if command == "right" => (Some(Token::Close), 1),
+                        Some('|') if command == "left" => {
+                            tokens.push((i, Token::Func(Func::Abs)));
+                            (Some(Token::Open), 1)
+                        }
+                        Some('|') if command == "right" => (Some(Token::Close), 1),
+                        _ => return Err(ParseErr::UnexpectedToken(i, format!("\\{command}"))),
+                    },
+                    "frac" if is_latex_derivative(&rest) => {
+                        (Some(Token::Derivative(rest.chars().nth(5).unwrap())), 7)
+                    }
+                    "frac" | "dfrac" => (Some(Token::Frac), 0),
+                    "times" | "cdot" => (Some(Token::Op(Op::Mul)), 0),
+                    "pi" => (Some(Token::Constant(Constant::Pi)), 0),
+                    "" if rest.starts_with([' ', ',', ';']) => (None, 1),
+                    command if func_from_name(command).is_some() => {
+                        let (func, end) = lex_func(&chars, i + 1, command)?;
+                        (Some(Token::Func(func)), end - (i + 1 + command.len()))
+                    }
+                    command => return Err(ParseErr::UnexpectedToken(i, format!("\\{command}"))),
+                };
+                if let Some(token) = token {
+                    tokens.push((i, token));
+                }
+                i += 1 + command.len() + len;
+                continue;
+            }
+            c if c.is_alphabetic() => {
+                let rest: String = chars[i..]
+                    .iter()
+                    .take_while(|c| c.is_alphabetic())
+                    .collect();
+                if let Some(name) = FUNC_NAMES.iter().find(|name| rest.starts_with(*name)) {
+                    let (func, end) = lex_func(&chars, i, name)?;
+                    tokens.push((i, Token::Func(func)));
+                    i = end;
+                } else {
+                    tokens.push((i, lex_letter(c)));
+                    i += 1;
+                }
+                continue;
+            }
+            '+' => Token::Op(Op::Add),
+            '-' => Token::Op(Op::Sub),
+            '/' => Token::Op(Op::Div),
+            '^' => Token::Op(Op::Pow),
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '{' => Token::OpenBrace,
+            '}' => Token::CloseBrace,
+            '=' => Token::Equals,
+            c => return Err(ParseErr::UnexpectedChar(i, c)),
+        };
+        tokens.push((i, token));
+        i += 1;
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<(usize, Token)>,
+    index: usize,
+    end: usize,
+}
+
+impl Parser {
+    fn new(tokens: Vec<(usize, Token)>, end: usize) -> Self {
+        Self {
+            tokens,
+            index: 0,
+            end,
+        }
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.index).map(|(_, token)| token)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map(|(pos, _)| *pos)
+            .unwrap_or(self.end)
+    }
+
+    fn next(&mut self) -> Result<(usize, Token), ParseErr> {
+        let token = self
+            .tokens
+            .get(self.index)
+            .cloned()
+            .ok_or(ParseErr::UnexpectedEnd(self.end))?;
+        self.index += 1;
+        Ok(token)
+    }
+
+    fn unexpected(&self) -> ParseErr {
+        match self.tokens.get(self.index) {
+            Some((pos, token)) => ParseErr::UnexpectedToken(*pos, format!("{:?}", token)),
+            None => ParseErr::UnexpectedEnd(self.end),
+        }
+    }
+
+    fn expect_close(&mut self, open_pos: usize) -> Result<(), ParseErr> {
+        match self.peek() {
+            Some(Token::Close) => {
+                self.index += 1;
+                Ok(())
+            }
+            None => Err(ParseErr::UnclosedBracket(open_pos)),
+            Some(_) => Err(self.unexpected()),
+        }
+    }
+
+    fn parse_expr(&mut self, min_precedence: u64) -> Result<Expr, ParseErr> {
+        let mut left = self.parse_unary(min_precedence)?;
+
+        loop {
+            let (op, implicit) = match self.peek() {
+                Some(Token::Op(op)) => (*op, false),
+                Some(token) if token.starts_operand() => (Op::Mul, true),
+                _ => break,
+            };
+            if op.precedence() < min_precedence {
+                break;
+            }
+            if !implicit {
+                self.index += 1;
+            }
+
+            // powers are right associative, everything else groups to the left
+            let next_precedence = if op == Op::Pow {
+                op.precedence()
+            } else {
+                op.precedence() + 1
+            };
+            let right = self.parse_expr(next_precedence)?;
+            left = Pair::new(left, op, right).into();
+        }
+
+        Ok(left)
+    }
+
+    fn parse_unary(&mut self, min_precedence: u64) -> Result<Expr, ParseErr> {
+        match self.peek() {
+            Some(Token::Op(Op::Sub)) => {
+                self.index += 1;
+                let inner = self.parse_expr(min_precedence.max(Op::Mul.precedence()))?;
+                Ok(match inner {
+                    Expr::Rational(rational) => match rational.checked_neg() {
+                        Ok(negated) => Expr::Rational(negated),
+                        Err(_) => Expr::Negative(Box::new(Expr::Rational(rational))),
+                    },
+                    inner => Expr::Negative(Box::new(inner)),
+                })
+            }
+            Some(Token::Op(Op::Add)) => {
+                self.index += 1;
+                self.parse_expr(min_precedence.max(Op::Mul.precedence()))
+            }
+            _ => self.parse_atom(),
+        }
+    }
+
+    fn parse_bracketed(&mut self) -> Result<Expr, ParseErr> {
+        let open_pos = self.position();
+        match self.next()? {
+            (_, Token::Open) => (),
+            _ => {
+                self.index -= 1;
+                return Err(self.unexpected());
+            }
+        }
+        let inner = self.parse_expr(0)?;
+        self.expect_close(open_pos)?;
+        Ok(inner)
+    }
+
+    fn parse_braced(&mut self) -> Result<Expr, ParseErr> {
+        let open_pos = self.position();
+        match self.next()? {
+            (_, Token::OpenBrace) => (),
+            _ => {
+                self.index -= 1;
+                return Err(self.unexpected());
+            }
+        }
+        let inner = self.parse_expr(0)?;
+        match self.peek() {
+            Some(Token::CloseBrace) => self.index += 1,
+            None => return Err(ParseErr::UnclosedBracket(open_pos)),
+            Some(_) => return Err(self.unexpected()),
+        }
+        Ok(inner)
+    }
+
+    fn parse_fraction(&mut self) -> Result<Expr, ParseErr> {
+        let numerator = self.parse_braced()?;
+        let denominator = self.parse_braced()?;
+
+        // `LatexConvertible` writes every non-integer rational as a fraction in lowest terms, so
+        // any other fraction of integers is a division which was written out as a question
+        match (&numerator, &denominator) {
+            (Expr::Rational(n), Expr::Rational(d))
+                if n.denominator == 1
+                    && d.denominator == 1
+                    && d.numerator > 1
+                    && gcd(n.numerator.unsigned_abs() as u128, d.numerator as u128) == 1 =>
+            {
+                Ok(Rational::new(n.numerator, d.numerator as u64).into())
+            }
+            _ => Ok(Pair::new(numerator, Op::Div, denominator).into()),
+        }
+    }
+
+    fn parse_atom(&mut self) -> Result<Expr, ParseErr> {
+        let expr = match self.peek() {
+            Some(Token::Number(rational)) => {
+                let rational = *rational;
+                self.index += 1;
+                rational.into()
+            }
+            Some(Token::Variable(var)) => {
+                let var = *var;
+                self.index += 1;
+                Expr::Variable(var)
+            }
+            Some(Token::Constant(constant)) => {
+                let constant = *constant;
+                self.index += 1;
+                Expr::Constant(constant)
+            }
+            Some(Token::Func(func)) => {
+                let func = *func;
+                self.index += 1;
+                // `\sqrt{x}` takes its argument in braces
+                let inner = match self.peek() {
+                    Some(Token::OpenBrace) => self.parse_braced()?,
+                    _ => self.parse_bracketed()?,
+                };
+                Expr::Func(func, Box::new(inner))
+            }
+            Some(Token::Derivative(var)) => {
+                let var = *var;
+                self.index += 1;
+                Expr::Derivative(Box::new(self.parse_bracketed()?), var)
+            }
+            Some(Token::Open) => self.parse_bracketed()?,
+            Some(Token::OpenBrace) => self.parse_braced()?,
+            Some(Token::Frac) => {
+                self.index += 1;
+                self.parse_fraction()?
+            }
+            _ => return Err(self.unexpected()),
+        };
+
+        Ok(expr)
+    }
+
+    fn finish(&self) -> Result<(), ParseErr> {
+        if self.index < self.tokens.len() {
+            Err(self.unexpected())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Parses plain text maths such as `3x^2 - (x+1)/4`. Juxtaposition (`2x`, `3(x+1)`) is read as
+/// multiplication with the same precedence as `*`.
+pub fn parse_expr(input: &str) -> Result<Expr, ParseErr> {
+    let mut parser = Parser::new(lex(input)?, input.chars().count());
+    let expr = parser.parse_expr(0)?;
+    parser.finish()?;
+    Ok(expr)
+}
+
+pub fn parse_equation(input: &str) -> Result<Equation, ParseErr> {
+    parse_equation_tokens(lex(input)?, input.chars().count())
+}
+
+/// Parses the LaTeX subset written by `LatexConvertible` (`\frac`, `\left(\right)`, `^{}`,
+/// `\times`, `\frac{d}{dx}` and `\sin`/`\cos`).
+pub fn parse_latex(input: &str) -> Result<Expr, ParseErr> {
+    let mut parser = Parser::new(lex_latex(input)?, input.chars().count());
+    let expr = parser.parse_expr(0)?;
+    parser.finish()?;
+    Ok(expr)
+}
+
+pub fn parse_latex_equation(input: &str) -> Result<Equation, ParseErr> {
+    parse_equation_tokens(lex_latex(input)?, input.chars().count())
+}
+
+fn parse_equation_tokens(tokens: Vec<(usize, Token)>, end: usize) -> Result<Equation, ParseErr> {
+    let mut parser = Parser::new(tokens, end);
+    let lhs = parser.parse_expr(0)?;
+    match parser.peek() {
+        Some(Token::Equals) => parser.index += 1,
+        None => return Err(ParseErr::MissingEquals),
+        Some(_) => return Err(parser.unexpected()),
+    }
+    let rhs = parser.parse_expr(0)?;
+    parser.finish()?;
+    Ok(Equation { lhs, rhs })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        canonical::same,
+        gen::{gen_arithmetic, gen_derivable, rand_int, seeded_rng},
+        render::LatexConvertible,
+        simplify::simplify,
+    };
+
+    #[test]
+    fn latex_round_trips_generated_expressions() {
+        for seed in 0..300 {
+            let mut rng = seeded_rng(seed);
+            let answer = rand_int(&mut rng);
+            let arithmetic = gen_arithmetic(&mut rng, 2, answer);
+            let derivable = gen_derivable(&mut rng, 3);
+
+            for expr in [arithmetic, derivable] {
+                let latex = expr.to_latex();
+                let parsed = parse_latex(&latex).expect("LaTeX output should parse");
+                // brackets around an associative operation aren't written, so `a + (b - c)`
+                // comes back as `a + b - c`
+                assert!(same(&parsed, &expr), "{}", latex);
+                assert!(same(&simplify(&parsed), &simplify(&expr)), "{}", latex);
+            }
+        }
+    }
+
+    #[test]
+    fn fractions_in_lowest_terms_are_rationals() {
+        assert_eq!(parse_latex("\\frac{3}{4}"), Ok(Rational::new(3, 4).into()));
+        assert_eq!(
+            parse_latex("\\frac{-3}{4}"),
+            Ok(Rational::new(-3, 4).into())
+        );
+    }
+
+    #[test]
+    fn other_fractions_of_integers_are_divisions() {
+        let division = |n: i64, d: i64| -> Expr {
+            Pair::new(Rational::int(n).into(), Op::Div, Rational::int(d).into()).into()
+        };
+        assert_eq!(parse_latex("\\frac{14}{7}"), Ok(division(14, 7)));
+        assert_eq!(parse_latex("\\frac{2}{4}"), Ok(division(2, 4)));
+        assert_eq!(parse_latex("\\frac{3}{1}"), Ok(division(3, 1)));
+    }
+
+    fn pair(left: Expr, op: Op, right: Expr) -> Expr {
+        Pair::new(left, op, right).into()
+    }
+
+    fn x() -> Expr {
+        Expr::Variable('x')
+    }
+
+    fn int(n: i64) -> Expr {
+        Rational::int(n).into()
+    }
+
+    #[test]
+    fn precedence_matches_the_operators() {
+        assert_eq!(
+            parse_expr("1 + 2*x^3"),
+            Ok(pair(
+                int(1),
+                Op::Add,
+                pair(int(2), Op::Mul, pair(x(), Op::Pow, int(3)))
+            ))
+        );
+        assert_eq!(parse_expr("1 - 2 - x"), parse_expr("(1 - 2) - x"));
+        assert_eq!(parse_expr("x/2*3"), parse_expr("(x/2)*3"));
+        assert_eq!(parse_expr("2^3^x"), parse_expr("2^(3^x)"));
+        assert_eq!(parse_expr("-x^2"), parse_expr("-(x^2)"));
+        assert!(Op::Pow.precedence() > Op::Mul.precedence());
+        assert!(Op::Mul.precedence() > Op::Add.precedence());
+    }
+
+    #[test]
+    fn juxtaposition_is_multiplication() {
+        assert_eq!(parse_expr("2x"), Ok(pair(int(2), Op::Mul, x())));
+        assert_eq!(
+            parse_expr("3(x+1)"),
+            Ok(pair(int(3), Op::Mul, pair(x(), Op::Add, int(1))))
+        );
+        assert_eq!(parse_expr("2x^2"), parse_expr("2*(x^2)"));
+        assert_eq!(parse_expr("2sin(x)"), parse_expr("2*sin(x)"));
+    }
+
+    #[test]
+    fn functions_take_a_bracketed_argument() {
+        assert_eq!(
+            parse_expr("sin(x)"),
+            Ok(Expr::Func(Func::Sine, Box::new(x())))
+        );
+        assert_eq!(
+            parse_expr("cos(2x + 1)"),
+            Ok(Expr::Func(
+                Func::Cosine,
+                Box::new(pair(pair(int(2), Op::Mul, x()), Op::Add, int(1)))
+            ))
+        );
+        assert_eq!(parse_expr("sin(x)^2"), parse_expr("(sin(x))^2"));
+    }
+
+    #[test]
+    fn decimals_are_exact() {
+        assert_eq!(parse_expr("1.25"), Ok(Rational::new(5, 4).into()));
+        assert_eq!(parse_expr(".5"), Ok(Rational::new(1, 2).into()));
+    }
+
+    #[test]
+    fn equations_split_at_the_equals_sign() {
+        assert_eq!(
+            parse_equation("2x + 1 = 5"),
+            Ok(Equation {
+                lhs: pair(pair(int(2), Op::Mul, x()), Op::Add, int(1)),
+                rhs: int(5),
+            })
+        );
+        assert_eq!(parse_equation("2x + 1"), Err(ParseErr::MissingEquals));
+    }
+
+    #[test]
+    fn malformed_input_reports_where() {
+        assert_eq!(parse_expr("2 $ 3"), Err(ParseErr::UnexpectedChar(2, '$')));
+        assert_eq!(parse_expr("(x + 1"), Err(ParseErr::UnclosedBracket(0)));
+        assert_eq!(parse_expr("x +"), Err(ParseErr::UnexpectedEnd(3)));
+        assert_eq!(parse_expr("x + )").unwrap_err().position(), Some(4));
+        assert_eq!(parse_expr("."), Err(ParseErr::UnexpectedChar(0, '.')));
+        assert_eq!(parse_expr("x + ."), Err(ParseErr::UnexpectedChar(4, '.')));
+        assert_eq!(parse_expr("1.2.3"), Err(ParseErr::UnexpectedChar(3, '.')));
+        assert_eq!(
+            parse_expr("99999999999999999999"),
+            Err(ParseErr::NumberTooLarge(0))
+        );
+    }
+}
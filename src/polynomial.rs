@@ -0,0 +1,558 @@
+use crate::{
+    derive::derive,
+    eval::{eval, gcd, EvalErr},
+    Expr, Op, Pair, Rational,
+};
+
+// higher powers are refused rather than expanded
+const MAX_DEGREE: i64 = 16;
+// the rational root theorem only tries divisors of coefficients up to this size
+const MAX_CANDIDATE_FACTOR: i64 = 100_000;
+
+#[derive(Debug)]
+pub enum PolynomialErr {
+    NotPolynomial(Expr),
+    FailedToEval(EvalErr),
+}
+
+/// A polynomial in `var` with exact coefficients, lowest power first and without leading zeros.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Polynomial {
+    pub var: char,
+    pub coefficients: Vec<Rational>,
+}
+
+/// `constant * f1^n1 * f2^n2 * ...`, where every factor is a primitive polynomial.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Factors {
+    pub constant: Rational,
+    pub factors: Vec<(Polynomial, u32)>,
+}
+
+fn is_zero(rational: &Rational) -> bool {
+    rational.numerator == 0
+}
+
+fn pair(l: Expr, op: Op, r: Expr) -> Expr {
+    Pair::new(l, op, r).into()
+}
+
+fn divisors(n: i64) -> Vec<i64> {
+    (1..=n.abs()).filter(|d| n % d == 0).collect()
+}
+
+fn integer_sqrt(n: i64) -> Option<i64> {
+    if n < 0 {
+        return None;
+    }
+    let guess = (n as f64).sqrt().round() as i64;
+    (guess.saturating_sub(1)..=guess + 1).find(|root| root.checked_mul(*root) == Some(n))
+}
+
+impl Polynomial {
+    pub fn new(var: char, mut coefficients: Vec<Rational>) -> Polynomial {
+        while coefficients.last().is_some_and(is_zero) {
+            coefficients.pop();
+        }
+        Polynomial { var, coefficients }
+    }
+
+    pub fn constant(var: char, constant: Rational) -> Polynomial {
+        Polynomial::new(var, vec![constant])
+    }
+
+    /// `var` itself.
+    pub fn identity(var: char) -> Polynomial {
+        Polynomial::new(var, vec![Rational::int(0), Rational::int(1)])
+    }
+
+    /// Multiplies out `expr`, which may only add, subtract and multiply polynomials, divide them
+    /// by constants and raise them to whole powers.
+    pub fn from_expr(expr: &Expr, var: char) -> Result<Polynomial, PolynomialErr> {
+        let not_polynomial = || PolynomialErr::NotPolynomial(expr.to_owned());
+        // any other unknown would otherwise only fail once it couldn't be evaluated
+        if expr.variables().iter().any(|&other| other != var) {
+            return Err(not_polynomial());
+        }
+        if expr.unknown_count_of(var) == 0 {
+            let value = eval(expr).map_err(PolynomialErr::FailedToEval)?;
+            return Ok(Polynomial::constant(var, value));
+        }
+
+        let polynomial = match expr {
+            Expr::Variable(_) => Ok(Polynomial::identity(var)),
+            Expr::Negative(inner) => Polynomial::from_expr(inner, var)?.scale(Rational::int(-1)),
+            Expr::Pair(pair) if pair.op == Op::Pow => {
+                if pair.right.unknown_count_of(var) != 0 {
+                    return Err(not_polynomial());
+                }
+                let exponent = eval(&pair.right).map_err(PolynomialErr::FailedToEval)?;
+                if exponent.denominator != 1 || !(0..=MAX_DEGREE).contains(&exponent.numerator) {
+                    return Err(not_polynomial());
+                }
+
+                Polynomial::from_expr(&pair.left, var)?.checked_pow(exponent.numerator as u32)
+            }
+            Expr::Pair(pair) => {
+                let left = Polynomial::from_expr(&pair.left, var)?;
+                let right = Polynomial::from_expr(&pair.right, var)?;
+                match pair.op {
+                    Op::Add => left.checked_add(&right),
+                    Op::Sub => left.checked_sub(&right),
+                    Op::Mul => left.checked_mul(&right),
+                    Op::Div if right.degree() == Some(0) => Rational::int(1)
+                        .checked_div(right.coefficients[0])
+                        .and_then(|reciprocal| left.scale(reciprocal)),
+                    _ => return Err(not_polynomial()),
+                }
+            }
+            Expr::Derivative(inner, wrt) => {
+                return Polynomial::from_expr(&derive(inner, *wrt), var)
+            }
+            Expr::Rational(_) | Expr::Constant(_) | Expr::Func(..) => return Err(not_polynomial()),
+        };
+
+        let polynomial = polynomial.map_err(PolynomialErr::FailedToEval)?;
+        if polynomial.coefficients.len() as i64 > MAX_DEGREE + 1 {
+            return Err(not_polynomial());
+        }
+        Ok(polynomial)
+    }
+
+    /// The highest power, which the zero polynomial doesn't have.
+    pub fn degree(&self) -> Option<usize> {
+        self.coefficients.len().checked_sub(1)
+    }
+
+    pub fn leading(&self) -> Rational {
+        self.coefficients
+            .last()
+            .copied()
+            .unwrap_or(Rational::int(0))
+    }
+
+    pub fn value_at(&self, x: f64) -> f64 {
+        self.coefficients.iter().rev().fold(0.0, |acc, c| {
+            acc * x + c.numerator as f64 / c.denominator as f64
+        })
+    }
+
+    pub fn checked_add(&self, rhs: &Polynomial) -> Result<Polynomial, EvalErr> {
+        let coefficients = (0..self.coefficients.len().max(rhs.coefficients.len()))
+            .map(|i| {
+                let l = self.coefficients.get(i).copied();
+                let r = rhs.coefficients.get(i).copied();
+                l.unwrap_or(Rational::int(0))
+                    .checked_add(r.unwrap_or(Rational::int(0)))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Polynomial::new(self.var, coefficients))
+    }
+
+    pub fn checked_sub(&self, rhs: &Polynomial) -> Result<Polynomial, EvalErr> {
+        self.checked_add(&rhs.scale(Rational::int(-1))?)
+    }
+
+    pub fn checked_mul(&self, rhs: &Polynomial) -> Result<Polynomial, EvalErr> {
+        if self.coefficients.is_empty() || rhs.coefficients.is_empty() {
+            return Ok(Polynomial::new(self.var, vec![]));
+        }
+
+        let mut product =
+            vec![Rational::int(0); self.coefficients.len() + rhs.coefficients.len() - 1];
+        for (i, l) in self.coefficients.iter().enumerate() {
+            for (j, r) in rhs.coefficients.iter().enumerate() {
+                product[i + j] = product[i + j].checked_add(l.checked_mul(*r)?)?;
+            }
+        }
+        Ok(Polynomial::new(self.var, product))
+    }
+
+    pub fn checked_pow(&self, exponent: u32) -> Result<Polynomial, EvalErr> {
+        (0..exponent).try_fold(
+            Polynomial::constant(self.var, Rational::int(1)),
+            |power, _| power.checked_mul(self),
+        )
+    }
+
+    pub fn scale(&self, factor: Rational) -> Result<Polynomial, EvalErr> {
+        let coefficients = self
+            .coefficients
+            .iter()
+            .map(|c| c.checked_mul(factor))
+            .collect::<Result<_, _>>()?;
+        Ok(Polynomial::new(self.var, coefficients))
+    }
+
+    /// Synthetic division by `var - root`, giving the quotient and the remainder.
+    pub fn divide_by_root(&self, root: Rational) -> Result<(Polynomial, Rational), EvalErr> {
+        let mut quotient = vec![];
+        let mut carry = Rational::int(0);
+        for coefficient in self.coefficients.iter().rev() {
+            carry = carry.checked_mul(root)?.checked_add(*coefficient)?;
+            quotient.push(carry);
+        }
+
+        let remainder = quotient.pop().unwrap_or(Rational::int(0));
+        quotient.reverse();
+        Ok((Polynomial::new(self.var, quotient), remainder))
+    }
+
+    // the positive rational which leaves coprime integer coefficients when divided out
+    fn content(&self) -> Result<Rational, EvalErr> {
+        let denominators = self.coefficients.iter().try_fold(1u128, |lcm, c| {
+            let denominator = c.denominator as u128;
+            (lcm / gcd(lcm, denominator))
+                .checked_mul(denominator)
+                .ok_or(EvalErr::Overflow)
+        })?;
+        let numerators = self
+            .coefficients
+            .iter()
+            .map(|c| c.numerator.unsigned_abs() as u128)
+            .fold(0, gcd);
+        let denominators = u64::try_from(denominators).map_err(|_| EvalErr::Overflow)?;
+        let numerators = i64::try_from(numerators).map_err(|_| EvalErr::Overflow)?;
+        Ok(Rational::new(numerators, denominators))
+    }
+
+    /// Every possible rational root, `±p/q` where `p` divides the constant term and `q` the
+    /// leading coefficient once the coefficients are scaled to integers.
+    pub fn rational_root_candidates(&self) -> Result<Vec<Rational>, EvalErr> {
+        if self.coefficients.len() < 2 {
+            return Ok(vec![]);
+        }
+
+        let primitive = self.scale(Rational::int(1).checked_div(self.content()?)?)?;
+        let constant = primitive.coefficients[0].numerator;
+        let leading = primitive.leading().numerator;
+        if constant.abs() > MAX_CANDIDATE_FACTOR || leading.abs() > MAX_CANDIDATE_FACTOR {
+            return Ok(vec![]);
+        }
+
+        let mut candidates = vec![];
+        for p in divisors(constant) {
+            for q in divisors(leading) {
+                let candidate = Rational::new(p, q as u64);
+                if !candidates.contains(&candidate) {
+                    candidates.push(candidate);
+                    candidates.push(candidate.checked_neg()?);
+                }
+            }
+        }
+        candidates.sort_by_key(|candidate| (candidate.numerator.abs(), candidate.denominator));
+        Ok(candidates)
+    }
+
+    // `a x^2k - b` as `(sqrt(a) x^k - sqrt(b))(sqrt(a) x^k + sqrt(b))`, for integer coefficients
+    fn difference_of_squares(&self) -> Option<(Polynomial, Polynomial)> {
+        let degree = self.degree()?;
+        let constant = self.coefficients[0];
+        let leading = self.leading();
+        let is_binomial = self.coefficients[1..degree].iter().all(is_zero);
+        if degree % 2 != 0 || !is_binomial || constant.denominator != 1 || leading.denominator != 1
+        {
+            return None;
+        }
+
+        let root_leading = integer_sqrt(leading.numerator)?;
+        let root_constant = integer_sqrt(-constant.numerator)?;
+        let half = |sign: i64| {
+            let mut coefficients = vec![Rational::int(0); degree / 2 + 1];
+            coefficients[0] = Rational::int(sign * root_constant);
+            coefficients[degree / 2] = Rational::int(root_leading);
+            Polynomial::new(self.var, coefficients)
+        };
+        Some((half(-1), half(1)))
+    }
+
+    // splits a primitive polynomial into irreducible factors, as far as the rules here can see
+    fn split(self, factors: &mut Vec<Polynomial>) -> Result<(), EvalErr> {
+        match self.degree() {
+            None | Some(0) => return Ok(()),
+            Some(1) => {
+                factors.push(self);
+                return Ok(());
+            }
+            _ => (),
+        }
+
+        if let Some((difference, sum)) = self.difference_of_squares() {
+            difference.split(factors)?;
+            return sum.split(factors);
+        }
+
+        for candidate in self.rational_root_candidates()? {
+            let (quotient, remainder) = self.divide_by_root(candidate)?;
+            if !is_zero(&remainder) {
+                continue;
+            }
+
+            // a root of p/q gives the integer factor `q x - p`, leaving an integer quotient
+            let denominator = Rational::int(candidate.denominator as i64);
+            let linear = Polynomial::new(
+                self.var,
+                vec![
+                    Rational::int(candidate.numerator).checked_neg()?,
+                    denominator,
+                ],
+            );
+            factors.push(linear);
+            return quotient
+                .scale(Rational::int(1).checked_div(denominator)?)?
+                .split(factors);
+        }
+
+        factors.push(self);
+        Ok(())
+    }
+
+    /// Factorises over the rationals by taking out a common factor, then splitting off
+    /// differences of squares and a linear factor for each rational root.
+    pub fn factor(&self) -> Result<Factors, EvalErr> {
+        if self.coefficients.is_empty() {
+            return Ok(Factors {
+                constant: Rational::int(0),
+                factors: vec![],
+            });
+        }
+
+        let mut constant = self.content()?;
+        if self.leading().numerator < 0 {
+            constant = constant.checked_neg()?;
+        }
+        let mut primitive = self.scale(Rational::int(1).checked_div(constant)?)?;
+
+        let mut found = vec![];
+        let zero_roots = primitive
+            .coefficients
+            .iter()
+            .take_while(|c| is_zero(c))
+            .count();
+        for _ in 0..zero_roots {
+            found.push(Polynomial::identity(self.var));
+        }
+        primitive.coefficients.drain(..zero_roots);
+        primitive.split(&mut found)?;
+
+        let mut factors: Vec<(Polynomial, u32)> = vec![];
+        for factor in found {
+            match factors.iter_mut().find(|(existing, _)| *existing == factor) {
+                Some((_, power)) => *power += 1,
+                None => factors.push((factor, 1)),
+            }
+        }
+        // `x` is written before any other factor of the same degree
+        factors.sort_by_key(|(factor, _)| {
+            let constant = factor.coefficients[0];
+            (factor.degree(), !is_zero(&constant), constant)
+        });
+
+        Ok(Factors { constant, factors })
+    }
+}
+
+// `c1 t1 + c2 t2 + ...` written the way it would be by hand, where a missing term is a constant
+fn signed_sum(terms: Vec<(Rational, Option<Expr>)>) -> Expr {
+    let mut sum: Option<Expr> = None;
+    for (coefficient, unknown) in terms {
+        if is_zero(&coefficient) {
+            continue;
+        }
+
+        // a coefficient too large to negate is added as it is
+        let (negative, magnitude) = match coefficient.checked_neg() {
+            Ok(magnitude) if coefficient.numerator < 0 => (true, magnitude),
+            _ => (false, coefficient),
+        };
+        let term = match unknown {
+            None => magnitude.into(),
+            Some(unknown) if magnitude == Rational::int(1) => unknown,
+            Some(unknown) => pair(magnitude.into(), Op::Mul, unknown),
+        };
+
+        sum = Some(match sum {
+            None if negative => Expr::Negative(Box::new(term)),
+            None => term,
+            Some(sum) if negative => pair(sum, Op::Sub, term),
+            Some(sum) => pair(sum, Op::Add, term),
+        });
+    }
+    sum.unwrap_or_else(|| Rational::int(0).into())
+}
+
+impl From<Polynomial> for Expr {
+    fn from(polynomial: Polynomial) -> Self {
+        let var = polynomial.var;
+        let terms = polynomial
+            .coefficients
+            .into_iter()
+            .enumerate()
+            .rev()
+            .map(|(power, coefficient)| {
+                let unknown = match power {
+                    0 => None,
+                    1 => Some(Expr::Variable(var)),
+                    _ => Some(pair(
+                        Expr::Variable(var),
+                        Op::Pow,
+                        Rational::int(power as i64).into(),
+                    )),
+                };
+                (coefficient, unknown)
+            })
+            .collect();
+        signed_sum(terms)
+    }
+}
+
+impl From<Factors> for Expr {
+    fn from(factors: Factors) -> Self {
+        let mut product: Option<Expr> = None;
+        for (factor, power) in factors.factors {
+            let factor = match power {
+                1 => factor.into(),
+                _ => pair(factor.into(), Op::Pow, Rational::int(power as i64).into()),
+            };
+            product = Some(match product {
+                None => factor,
+                Some(product) => pair(product, Op::Mul, factor),
+            });
+        }
+
+        match product {
+            None => factors.constant.into(),
+            Some(product) if factors.constant == Rational::int(1) => product,
+            Some(product) if factors.constant == Rational::int(-1) => {
+                Expr::Negative(Box::new(product))
+            }
+            Some(product) => pair(factors.constant.into(), Op::Mul, product),
+        }
+    }
+}
+
+// splits a sum into its terms, as a rational coefficient and whatever it multiplies, which is
+// `None` for constants, where `sign` is 1 or -1
+fn terms(expr: &Expr, sign: i64, split: &mut Vec<(Rational, Option<Expr>)>) {
+    let coefficient = |value: Rational| Rational::int(sign).checked_mul(value).ok();
+    match expr {
+        Expr::Pair(pair) if pair.op == Op::Add || pair.op == Op::Sub => {
+            terms(&pair.left, sign, split);
+            let sign = if pair.op == Op::Add { sign } else { -sign };
+            terms(&pair.right, sign, split);
+        }
+        Expr::Negative(inner) => terms(inner, -sign, split),
+        expr => {
+            if let Some(value) = eval(expr).ok().and_then(coefficient) {
+                split.push((value, None));
+                return;
+            }
+
+            let multiple = match expr {
+                Expr::Pair(pair) if pair.op == Op::Mul => {
+                    match (eval(&pair.left), eval(&pair.right)) {
+                        (Ok(value), _) => coefficient(value).map(|c| (c, pair.right.clone())),
+                        (_, Ok(value)) => coefficient(value).map(|c| (c, pair.left.clone())),
+                        _ => None,
+                    }
+                }
+                _ => None,
+            };
+            let (coefficient, term) = multiple.unwrap_or((Rational::int(sign), expr.to_owned()));
+            split.push((coefficient, Some(term)));
+        }
+    }
+}
+
+/// Collects like terms in a sum without multiplying anything out, so `2x + (x + 1)^2 + 3x`
+/// becomes `5x + (x + 1)^2`.
+pub fn collect(expr: &Expr) -> Result<Expr, EvalErr> {
+    let mut split = vec![];
+    terms(expr, 1, &mut split);
+
+    let mut collected: Vec<(Rational, Option<Expr>)> = vec![];
+    for (coefficient, term) in split {
+        match collected.iter_mut().find(|(_, existing)| *existing == term) {
+            Some((total, _)) => *total = total.checked_add(coefficient)?,
+            None => collected.push((coefficient, term)),
+        }
+    }
+    // the constant goes last, as it would be written
+    collected.sort_by_key(|(_, term)| term.is_none());
+
+    Ok(signed_sum(collected))
+}
+
+/// Multiplies out every bracket in `expr`, giving the polynomial in descending powers of `var`.
+pub fn expand(expr: &Expr, var: char) -> Result<Expr, PolynomialErr> {
+    Ok(Polynomial::from_expr(expr, var)?.into())
+}
+
+pub fn factor(expr: &Expr, var: char) -> Result<Expr, PolynomialErr> {
+    let factors = Polynomial::from_expr(expr, var)?
+        .factor()
+        .map_err(PolynomialErr::FailedToEval)?;
+    Ok(factors.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_expr;
+
+    fn factored(input: &str) -> String {
+        factor(&parse_expr(input).unwrap(), 'x')
+            .unwrap()
+            .to_string()
+    }
+
+    fn expanded(input: &str) -> String {
+        expand(&parse_expr(input).unwrap(), 'x')
+            .unwrap()
+            .to_string()
+    }
+
+    fn collected(input: &str) -> String {
+        collect(&parse_expr(input).unwrap()).unwrap().to_string()
+    }
+
+    #[test]
+    fn factors_over_the_rationals() {
+        assert_eq!(factored("x^4 - 1"), "(x - 1) * (x + 1) * (x ^ (2) + 1)");
+        assert_eq!(factored("x^2 - 5*x + 6"), "(x - 3) * (x - 2)");
+        assert_eq!(factored("2*x^2 - 8"), "2 * (x - 2) * (x + 2)");
+        assert_eq!(factored("6*x^2 + x - 1"), "(3 * x - 1) * (2 * x + 1)");
+        assert_eq!(factored("x^3 - x"), "x * (x - 1) * (x + 1)");
+        assert_eq!(factored("x^3 - 3*x^2 + 3*x - 1"), "(x - 1) ^ (3)");
+        assert_eq!(factored("x^2 + x + 1"), "x ^ (2) + x + 1");
+    }
+
+    #[test]
+    fn expands_brackets_in_descending_powers() {
+        assert_eq!(expanded("(x - 1)^3"), "x ^ (3) - 3 * x ^ (2) + 3 * x - 1");
+        assert_eq!(expanded("(x + 1)*(x - 1) + 2*x"), "x ^ (2) + 2 * x - 1");
+        assert_eq!(expanded("(2*x + 4)/2"), "x + 2");
+    }
+
+    #[test]
+    fn collects_like_terms_without_expanding() {
+        assert_eq!(collected("3*x + 2*y - x"), "2 * x + 2 * y");
+        assert_eq!(collected("x^2 + 2*x + x^2"), "2 * x ^ (2) + 2 * x");
+        assert_eq!(collected("2*x + (x + 1)^2 + 3*x"), "5 * x + (x + 1) ^ (2)");
+        assert_eq!(collected("x + 3 - x - 3"), "0");
+    }
+
+    #[test]
+    fn other_unknowns_and_functions_are_not_polynomial() {
+        for input in ["x*y", "x + 1/y", "sin(x)", "x^(1/2)", "1/x"] {
+            assert!(
+                matches!(
+                    Polynomial::from_expr(&parse_expr(input).unwrap(), 'x'),
+                    Err(PolynomialErr::NotPolynomial(_))
+                ),
+                "{}",
+                input
+            );
+        }
+    }
+}
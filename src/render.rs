@@ -1,4 +1,11 @@
-use crate::{Answer, Equation, Expr, Op, Pair, Rational};
+use crate::{
+    derive::Trace,
+    inequality::{Range, RangeSet},
+    integrate::Integral,
+    solve::{Reason, Root, Solution},
+    Answer, Comparison, Constant, Equation, ExactVal, Expr, Func, Op, Pair, Rational, Relation,
+    Surd, System,
+};
 use image::{io::Reader as ImageReader, ImageFormat};
 use reqwest::Client;
 use std::{
@@ -23,6 +30,50 @@ impl LatexConvertible for Rational {
     }
 }
 
+impl LatexConvertible for Surd {
+    fn to_latex(&self) -> String {
+        let root = if self.index == 2 {
+            format!("\\sqrt{{{}}}", self.radicand)
+        } else {
+            format!("\\sqrt[{}]{{{}}}", self.index, self.radicand)
+        };
+
+        let magnitude = Rational::new(
+            self.coefficient.numerator.abs(),
+            self.coefficient.denominator,
+        );
+        let sign = if self.coefficient.numerator < 0 {
+            "-"
+        } else {
+            ""
+        };
+        if magnitude == Rational::int(1) {
+            format!("{sign}{root}")
+        } else {
+            format!("{sign}{} {root}", magnitude.to_latex())
+        }
+    }
+}
+
+impl LatexConvertible for ExactVal {
+    fn to_latex(&self) -> String {
+        match self {
+            ExactVal::Rational(rational) => rational.to_latex(),
+            ExactVal::Surd(surd) => surd.to_latex(),
+            ExactVal::Multiple(multiple) => Expr::from(*multiple).to_latex(),
+        }
+    }
+}
+
+impl LatexConvertible for Constant {
+    fn to_latex(&self) -> String {
+        match self {
+            Constant::Pi => "\\pi".into(),
+            Constant::E => "e".into(),
+        }
+    }
+}
+
 fn latex_bracketize(s: &str) -> String {
     format!("\\left({s}\\right)")
 }
@@ -49,8 +100,9 @@ impl LatexConvertible for Pair {
                 }
                 match self.right {
                     Expr::Variable(_)
+                    | Expr::Constant(_)
                     | Expr::Pair(box Pair {
-                        left: Expr::Variable(_),
+                        left: Expr::Variable(_) | Expr::Constant(_),
                         op: Op::Pow,
                         ..
                     }) => {
@@ -74,20 +126,235 @@ impl LatexConvertible for Expr {
                 format!("-{}", expr.to_latex())
             }
             Expr::Variable(var) => var.to_owned().into(),
-            Expr::Derivative(expr) => {
-                format!("\\frac{{d}}{{dx}}\\left({}\\right)", expr.to_latex())
+            Expr::Constant(constant) => constant.to_latex(),
+            Expr::Derivative(expr, var) => {
+                format!("\\frac{{d}}{{d{}}}\\left({}\\right)", var, expr.to_latex())
+            }
+            Expr::Func(Func::Log(base), inner) => {
+                format!("\\log_{{{}}}\\left({}\\right)", base, inner.to_latex())
             }
-            Expr::Func(func, inner) => format!("{}\\left({}\\right)", func, inner.to_latex()),
+            Expr::Func(Func::Sqrt, inner) => format!("\\sqrt{{{}}}", inner.to_latex()),
+            Expr::Func(Func::Abs, inner) => format!("\\left|{}\\right|", inner.to_latex()),
+            Expr::Func(func, inner) => format!("\\{}\\left({}\\right)", func, inner.to_latex()),
         }
     }
 }
 
+impl LatexConvertible for Integral {
+    fn to_latex(&self) -> String {
+        let bounds = match &self.bounds {
+            Some((lower, upper)) => format!("_{{{}}}^{{{}}}", lower.to_latex(), upper.to_latex()),
+            None => String::new(),
+        };
+        format!(
+            "\\int{} {} \\, d{}",
+            bounds,
+            self.integrand.to_latex(),
+            self.var
+        )
+    }
+}
+
+impl LatexConvertible for Trace {
+    fn to_latex(&self) -> String {
+        let mut lines: Vec<String> = self
+            .steps
+            .iter()
+            .map(|step| {
+                format!(
+                    "\\frac{{d}}{{d{}}}\\left({}\\right) &= {} && \\text{{{}}}",
+                    self.var,
+                    step.expr.to_latex(),
+                    step.result.to_latex(),
+                    step.rule
+                )
+            })
+            .collect();
+        lines.push(format!("&= {}", self.result.to_latex()));
+        format!(
+            "\\begin{{aligned}} {} \\end{{aligned}}",
+            lines.join(" \\\\ ")
+        )
+    }
+}
+
+impl LatexConvertible for Reason {
+    fn to_latex(&self) -> String {
+        let around = |before: &str, expr: &Expr, after: &str| {
+            let mut latex = format!("\\text{{{}}} {}", before, expr.to_latex());
+            if !after.is_empty() {
+                latex += &format!(" \\text{{{}}}", after);
+            }
+            latex
+        };
+        match self {
+            Reason::Add(expr) => around("add ", expr, " to both sides"),
+            Reason::Subtract(expr) => around("subtract ", expr, " from both sides"),
+            Reason::Multiply(expr) => around("multiply both sides by ", expr, ""),
+            Reason::Divide(expr) => around("divide both sides by ", expr, ""),
+            Reason::SubtractFrom(expr) => around("subtract both sides from ", expr, ""),
+            Reason::DivideInto(expr) => around("divide ", expr, " by both sides"),
+            Reason::Root(expr) => format!(
+                "\\text{{raise both sides to the power }} \\frac{{1}}{{{}}}",
+                expr.to_latex()
+            ),
+            reason => format!("\\text{{{}}}", reason),
+        }
+    }
+}
+
+impl LatexConvertible for Root {
+    fn to_latex(&self) -> String {
+        match self {
+            Root::Exact(expr) => expr.to_latex(),
+            Root::Approximate(value) => format!("\\approx {:.6}", value),
+        }
+    }
+}
+
+impl LatexConvertible for Solution {
+    fn to_latex(&self) -> String {
+        let mut lines = vec![format!(
+            "{} &= {}",
+            self.equation.lhs.to_latex(),
+            self.equation.rhs.to_latex()
+        )];
+        for step in &self.steps {
+            lines.push(format!(
+                "{} &= {} && {}",
+                step.equation.lhs.to_latex(),
+                step.equation.rhs.to_latex(),
+                step.reason.to_latex()
+            ));
+        }
+        format!(
+            "\\begin{{aligned}} {} \\end{{aligned}}",
+            lines.join(" \\\\ ")
+        )
+    }
+}
+
 impl LatexConvertible for Equation {
     fn to_latex(&self) -> String {
         format!("{} = {}", self.lhs.to_latex(), self.rhs.to_latex())
     }
 }
 
+impl LatexConvertible for Relation {
+    fn to_latex(&self) -> String {
+        let s = match self {
+            Relation::Equal => "=",
+            Relation::NotEqual => "\\neq",
+            Relation::Less => "<",
+            Relation::LessEqual => "\\leq",
+            Relation::Greater => ">",
+            Relation::GreaterEqual => "\\geq",
+        };
+        s.to_string()
+    }
+}
+
+impl LatexConvertible for Comparison {
+    fn to_latex(&self) -> String {
+        format!(
+            "{} {} {}",
+            self.lhs.to_latex(),
+            self.relation.to_latex(),
+            self.rhs.to_latex()
+        )
+    }
+}
+
+impl LatexConvertible for Range {
+    fn to_latex(&self) -> String {
+        if let (true, Some(point)) = (self.is_point(), &self.lower) {
+            return format!("\\left\\{{{}\\right\\}}", point.value.to_latex());
+        }
+
+        let lower = match &self.lower {
+            Some(lower) if lower.closed => format!("\\left[{}", lower.value.to_latex()),
+            Some(lower) => format!("\\left({}", lower.value.to_latex()),
+            None => "\\left(-\\infty".to_string(),
+        };
+        let upper = match &self.upper {
+            Some(upper) if upper.closed => format!("{}\\right]", upper.value.to_latex()),
+            Some(upper) => format!("{}\\right)", upper.value.to_latex()),
+            None => "\\infty\\right)".to_string(),
+        };
+        format!("{}, {}", lower, upper)
+    }
+}
+
+// interval notation, such as `(-∞, 2] ∪ (3, ∞)`
+impl LatexConvertible for RangeSet {
+    fn to_latex(&self) -> String {
+        if self.ranges.is_empty() {
+            return "\\varnothing".to_string();
+        }
+
+        let ranges: Vec<String> = self.ranges.iter().map(|r| r.to_latex()).collect();
+        ranges.join(" \\cup ")
+    }
+}
+
+impl RangeSet {
+    /// Set-builder notation, such as `{x | x ≤ 2 or x > 3}`.
+    pub fn to_set_latex(&self) -> String {
+        let relation = |closed: bool, strict: Relation, inclusive: Relation| {
+            if closed { inclusive } else { strict }.to_latex()
+        };
+        let conditions: Vec<String> = self
+            .ranges
+            .iter()
+            .map(|range| match (&range.lower, &range.upper) {
+                (Some(point), _) if range.is_point() => {
+                    format!("{} = {}", self.var, point.value.to_latex())
+                }
+                (Some(lower), Some(upper)) => format!(
+                    "{} {} {} {} {}",
+                    lower.value.to_latex(),
+                    relation(lower.closed, Relation::Less, Relation::LessEqual),
+                    self.var,
+                    relation(upper.closed, Relation::Less, Relation::LessEqual),
+                    upper.value.to_latex()
+                ),
+                (Some(lower), None) => format!(
+                    "{} {} {}",
+                    self.var,
+                    relation(lower.closed, Relation::Greater, Relation::GreaterEqual),
+                    lower.value.to_latex()
+                ),
+                (None, Some(upper)) => format!(
+                    "{} {} {}",
+                    self.var,
+                    relation(upper.closed, Relation::Less, Relation::LessEqual),
+                    upper.value.to_latex()
+                ),
+                (None, None) => format!("{} \\in \\mathbb{{R}}", self.var),
+            })
+            .collect();
+
+        if conditions.is_empty() {
+            return "\\varnothing".to_string();
+        }
+        format!(
+            "\\left\\{{{} \\mid {}\\right\\}}",
+            self.var,
+            conditions.join(" \\text{ or } ")
+        )
+    }
+}
+
+impl LatexConvertible for System {
+    fn to_latex(&self) -> String {
+        let equations: Vec<String> = self.equations.iter().map(|e| e.to_latex()).collect();
+        format!(
+            "\\begin{{cases}} {} \\end{{cases}}",
+            equations.join(" \\\\ ")
+        )
+    }
+}
+
 impl<T: LatexConvertible> LatexConvertible for Answer<T> {
     fn to_latex(&self) -> String {
         format!("{}) \\ {}", self.option, self.answer.to_latex())
@@ -169,3 +436,58 @@ pub async fn render_to_file<T: LatexConvertible + ?Sized>(
     copy(&mut content, &mut file)?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::{parse_equation, parse_expr};
+
+    #[test]
+    fn functions_have_their_own_notation() {
+        let cases = [
+            ("tan(x)", "tan(x)", "\\tan\\left(x\\right)"),
+            ("exp(x)", "exp(x)", "\\exp\\left(x\\right)"),
+            ("ln(x)", "ln(x)", "\\ln\\left(x\\right)"),
+            ("log(x)", "log_10(x)", "\\log_{10}\\left(x\\right)"),
+            ("sqrt(x)", "sqrt(x)", "\\sqrt{x}"),
+            ("abs(x)", "abs(x)", "\\left|x\\right|"),
+            ("arcsin(x)", "arcsin(x)", "\\arcsin\\left(x\\right)"),
+            ("arccos(x)", "arccos(x)", "\\arccos\\left(x\\right)"),
+            ("arctan(x)", "arctan(x)", "\\arctan\\left(x\\right)"),
+        ];
+        for (input, display, latex) in cases {
+            let expr = parse_expr(input).unwrap();
+            assert_eq!(expr.to_string(), display);
+            assert_eq!(expr.to_latex(), latex);
+        }
+    }
+
+    #[test]
+    fn constants_are_written_as_symbols() {
+        let cases = [
+            ("pi", "π", "\\pi"),
+            ("e", "e", "e"),
+            ("3*pi/4", "3 * π / (4)", "\\frac{3 \\pi}{4}"),
+            ("e^x", "e ^ (x)", "e^{x}"),
+        ];
+        for (input, display, latex) in cases {
+            let expr = parse_expr(input).unwrap();
+            assert_eq!(expr.to_string(), display);
+            assert_eq!(expr.to_latex(), latex);
+        }
+    }
+
+    #[test]
+    fn systems_are_written_as_cases() {
+        let system = System {
+            equations: vec![
+                parse_equation("x + y = 5").unwrap(),
+                parse_equation("x - 2*y = 1").unwrap(),
+            ],
+        };
+        assert_eq!(
+            system.to_latex(),
+            "\\begin{cases} x + y = 5 \\\\ x - 2 y = 1 \\end{cases}"
+        );
+    }
+}
@@ -0,0 +1,281 @@
+use std::collections::HashMap;
+
+use crate::{canonical::same, Expr, Func, Op, Pair, Rational};
+
+// a rule set which never settles would otherwise rewrite forever
+const MAX_REWRITES: usize = 10_000;
+
+/// The shape of an expression, where named holes bind to the parts of the expression they cover.
+/// A name which appears more than once only matches if every part it covers is the same, up to
+/// the order of sums and products.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Pattern {
+    Any(&'static str),
+    Rational(&'static str),
+    Variable(&'static str),
+    Exact(Expr),
+    Pair(Box<Pattern>, Op, Box<Pattern>),
+    Negative(Box<Pattern>),
+    Func(Func, Box<Pattern>),
+}
+
+/// The parts of an expression bound to each name in a pattern.
+#[derive(Clone, PartialEq, Debug, Default)]
+pub struct Bindings(HashMap<&'static str, Expr>);
+
+#[derive(Clone, Debug)]
+pub enum Replacement {
+    Pattern(Pattern),
+    Compute(fn(&Bindings) -> Option<Expr>),
+}
+
+/// Replaces anything matching `pattern` for which `condition` holds. A computed replacement can
+/// still refuse to apply by giving `None`, such as when its arithmetic overflows.
+#[derive(Clone, Debug)]
+pub struct Rule {
+    pub name: &'static str,
+    pub pattern: Pattern,
+    pub condition: Option<fn(&Bindings) -> bool>,
+    pub replacement: Replacement,
+}
+
+impl Pattern {
+    pub fn exact<T: Into<Expr>>(expr: T) -> Pattern {
+        Pattern::Exact(expr.into())
+    }
+
+    pub fn pair(left: Pattern, op: Op, right: Pattern) -> Pattern {
+        Pattern::Pair(Box::new(left), op, Box::new(right))
+    }
+
+    pub fn negative(inner: Pattern) -> Pattern {
+        Pattern::Negative(Box::new(inner))
+    }
+
+    pub fn func(func: Func, inner: Pattern) -> Pattern {
+        Pattern::Func(func, Box::new(inner))
+    }
+
+    pub fn matches(&self, expr: &Expr) -> Option<Bindings> {
+        let mut bindings = Bindings::default();
+        self.bind(expr, &mut bindings).then_some(bindings)
+    }
+
+    fn bind(&self, expr: &Expr, bindings: &mut Bindings) -> bool {
+        match (self, expr) {
+            (Pattern::Any(name), expr)
+            | (Pattern::Rational(name), expr @ Expr::Rational(_))
+            | (Pattern::Variable(name), expr @ Expr::Variable(_)) => match bindings.0.get(name) {
+                Some(bound) => same(bound, expr),
+                None => {
+                    bindings.0.insert(name, expr.to_owned());
+                    true
+                }
+            },
+            (Pattern::Exact(expected), expr) => expected == expr,
+            (Pattern::Pair(left, op, right), Expr::Pair(pair)) => {
+                *op == pair.op
+                    && left.bind(&pair.left, bindings)
+                    && right.bind(&pair.right, bindings)
+            }
+            (Pattern::Negative(pattern), Expr::Negative(inner)) => pattern.bind(inner, bindings),
+            (Pattern::Func(func, pattern), Expr::Func(expr_func, inner)) => {
+                func == expr_func && pattern.bind(inner, bindings)
+            }
+            _ => false,
+        }
+    }
+
+    /// Fills the holes with whatever they were bound to, or `None` if any of them are unbound.
+    pub fn fill(&self, bindings: &Bindings) -> Option<Expr> {
+        Some(match self {
+            Pattern::Any(name) | Pattern::Rational(name) | Pattern::Variable(name) => {
+                bindings.get(name)?.to_owned()
+            }
+            Pattern::Exact(expr) => expr.to_owned(),
+            Pattern::Pair(left, op, right) => {
+                Pair::new(left.fill(bindings)?, *op, right.fill(bindings)?).into()
+            }
+            Pattern::Negative(inner) => Expr::Negative(Box::new(inner.fill(bindings)?)),
+            Pattern::Func(func, inner) => Expr::Func(*func, Box::new(inner.fill(bindings)?)),
+        })
+    }
+}
+
+impl Bindings {
+    pub fn get(&self, name: &str) -> Option<&Expr> {
+        self.0.get(name)
+    }
+
+    pub fn rational(&self, name: &str) -> Option<Rational> {
+        match self.get(name)? {
+            Expr::Rational(rational) => Some(*rational),
+            _ => None,
+        }
+    }
+}
+
+impl Rule {
+    pub fn new(name: &'static str, pattern: Pattern, replacement: Pattern) -> Rule {
+        Rule {
+            name,
+            pattern,
+            condition: None,
+            replacement: Replacement::Pattern(replacement),
+        }
+    }
+
+    pub fn computed(
+        name: &'static str,
+        pattern: Pattern,
+        replacement: fn(&Bindings) -> Option<Expr>,
+    ) -> Rule {
+        Rule {
+            name,
+            pattern,
+            condition: None,
+            replacement: Replacement::Compute(replacement),
+        }
+    }
+
+    pub fn when(mut self, condition: fn(&Bindings) -> bool) -> Rule {
+        self.condition = Some(condition);
+        self
+    }
+
+    /// Rewrites `expr` itself, without looking any deeper, if the rule applies to it.
+    pub fn apply(&self, expr: &Expr) -> Option<Expr> {
+        let bindings = self.pattern.matches(expr)?;
+        if self
+            .condition
+            .is_some_and(|condition| !condition(&bindings))
+        {
+            return None;
+        }
+
+        match &self.replacement {
+            Replacement::Pattern(pattern) => pattern.fill(&bindings),
+            Replacement::Compute(compute) => compute(&bindings),
+        }
+    }
+}
+
+/// Rewrites `expr` from the bottom up with the first of `rules` to apply at each point, until none
+/// of them apply anywhere. A rule set can be as small as a single rule, to show just one
+/// simplification.
+pub fn rewrite(expr: &Expr, rules: &[Rule]) -> Expr {
+    let mut remaining = MAX_REWRITES;
+    rewrite_bounded(expr, rules, &mut remaining)
+}
+
+fn rewrite_bounded(expr: &Expr, rules: &[Rule], remaining: &mut usize) -> Expr {
+    let mut expr = rewrite_parts(expr, rules, remaining);
+    // a loop rather than recursion, so that a rule set which never settles can't overflow the
+    // stack before reaching the cut-off
+    while *remaining > 0 {
+        match rules.iter().find_map(|rule| rule.apply(&expr)) {
+            Some(rewritten) => {
+                *remaining -= 1;
+                // the parts of the replacement may now be rewritable in turn
+                expr = rewrite_parts(&rewritten, rules, remaining);
+            }
+            None => break,
+        }
+    }
+    expr
+}
+
+fn rewrite_parts(expr: &Expr, rules: &[Rule], remaining: &mut usize) -> Expr {
+    match expr {
+        Expr::Pair(pair) => Pair::new(
+            rewrite_bounded(&pair.left, rules, remaining),
+            pair.op,
+            rewrite_bounded(&pair.right, rules, remaining),
+        )
+        .into(),
+        Expr::Negative(inner) => Expr::Negative(Box::new(rewrite_bounded(inner, rules, remaining))),
+        Expr::Func(func, inner) => {
+            Expr::Func(*func, Box::new(rewrite_bounded(inner, rules, remaining)))
+        }
+        Expr::Derivative(inner, var) => {
+            Expr::Derivative(Box::new(rewrite_bounded(inner, rules, remaining)), *var)
+        }
+        Expr::Rational(_) | Expr::Variable(_) | Expr::Constant(_) => expr.to_owned(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse::parse_expr, simplify::simplification_rules};
+
+    fn parse(input: &str) -> Expr {
+        parse_expr(input).unwrap()
+    }
+
+    fn add_zero() -> Rule {
+        Rule::new(
+            "add zero",
+            Pattern::pair(Pattern::Any("e"), Op::Add, Pattern::exact(Rational::int(0))),
+            Pattern::Any("e"),
+        )
+    }
+
+    #[test]
+    fn repeated_names_match_the_same_expression() {
+        let pattern = Pattern::pair(Pattern::Any("e"), Op::Sub, Pattern::Any("e"));
+        let bindings = pattern.matches(&parse("(x + 1) - (1 + x)")).unwrap();
+        assert_eq!(bindings.get("e"), Some(&parse("x + 1")));
+        assert_eq!(pattern.matches(&parse("(x + 1) - (x + 2)")), None);
+
+        let pattern = Pattern::pair(Pattern::Rational("a"), Op::Mul, Pattern::Variable("x"));
+        assert!(pattern.matches(&parse("3*y")).is_some());
+        assert!(pattern.matches(&parse("y*3")).is_none());
+    }
+
+    #[test]
+    fn a_custom_rule_set_only_applies_its_own_rules() {
+        assert_eq!(
+            rewrite(&parse("(x + 0) + 2*3"), &[add_zero()]),
+            parse("x + 2*3")
+        );
+        assert_eq!(
+            rewrite(&parse("sin(y + 0) + 0"), &[add_zero()]),
+            parse("sin(y)")
+        );
+
+        let double = Rule::computed("double", Pattern::Rational("n"), |bindings| {
+            let n = bindings.rational("n")?;
+            Some(n.checked_mul(Rational::int(2)).ok()?.into())
+        })
+        .when(|bindings| {
+            bindings
+                .rational("n")
+                .is_some_and(|n| n < Rational::int(10))
+        });
+        assert_eq!(rewrite(&parse("3 + x"), &[double]), parse("12 + x"));
+    }
+
+    #[test]
+    fn the_first_rule_to_apply_wins() {
+        let rules = simplification_rules();
+        let expr = parse("x*0");
+        let rule = rules
+            .iter()
+            .find(|rule| rule.apply(&expr).is_some())
+            .unwrap();
+        assert_eq!(rule.name, "multiply by zero");
+    }
+
+    #[test]
+    fn rule_sets_which_never_settle_are_cut_off() {
+        let swap = Rule::new(
+            "swap",
+            Pattern::pair(Pattern::Any("a"), Op::Add, Pattern::Any("b")),
+            Pattern::pair(Pattern::Any("b"), Op::Add, Pattern::Any("a")),
+        );
+        // every rewrite swaps the operands, so an even number of them leaves `x + y` as it was
+        assert_eq!(MAX_REWRITES % 2, 0);
+        assert_eq!(rewrite(&parse("x + y"), &[swap]), parse("x + y"));
+    }
+}
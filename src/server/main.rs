@@ -4,12 +4,14 @@ extern crate rocket;
 use maths_problem_gen::derive::derive;
 use maths_problem_gen::gen::{
     gen_arithmetic, gen_backtrack, gen_polynomial, gen_polynomial_choices, gen_derivable,
+    gen_exact_trig, gen_exact_trig_choices, gen_integral, gen_integral_choices,
+    gen_system, gen_system_choices, seeded_rng,
 };
 use maths_problem_gen::render::LatexConvertible;
 use maths_problem_gen::simplify::simplify;
-use maths_problem_gen::Rational;
+use maths_problem_gen::{Rational, System};
 use maths_problem_gen::{gen::gen_choices, render::render_to_file, Answer, Equation, Expr};
-use rand::Rng;
+use rand::{seq::SliceRandom, Rng};
 use rocket::fs::NamedFile;
 use rocket::{
     serde::{json::Json, Serialize},
@@ -22,13 +24,17 @@ use std::{error::Error, fs, path::Path};
 type Maths = Box<dyn LatexConvertible + Send + Sync>;
 type Choice = Answer<Maths>;
 
-fn generate_multiple_choice_problem(level: u64) -> Result<(Maths, Vec<Choice>, usize), String> {
+fn generate_multiple_choice_problem(
+    level: u64,
+    seed: u64,
+) -> Result<(Maths, Vec<Choice>, usize), String> {
+    let mut rng = seeded_rng(seed);
     let (problem, answer, mut choices): (Maths, Maths, Vec<Maths>) = match level {
         1 => {
-            let answer = Rational::int(rand::thread_rng().gen_range(1..20));
-            let problem = gen_arithmetic(2, answer);
+            let answer = Rational::int(rng.gen_range(1..20));
+            let problem = gen_arithmetic(&mut rng, 2, answer);
 
-            let choices = gen_choices(answer, 3)
+            let choices = gen_choices(&mut rng, answer, 3)
                 .into_iter()
                 .map(|c| -> Maths { Box::new(c) })
                 .collect();
@@ -40,9 +46,9 @@ fn generate_multiple_choice_problem(level: u64) -> Result<(Maths, Vec<Choice>, u
             )
         }
         2 => {
-            let (equation, answer) = gen_backtrack(2);
+            let (equation, answer) = gen_backtrack(&mut rng, 2, false);
 
-            let choices: Vec<Maths> = gen_choices(answer, 3)
+            let choices: Vec<Maths> = gen_choices(&mut rng, answer, 3)
                 .into_iter()
                 .map(|x| Equation {
                     lhs: Expr::Variable('x'),
@@ -59,20 +65,71 @@ fn generate_multiple_choice_problem(level: u64) -> Result<(Maths, Vec<Choice>, u
             (Box::new(equation), Box::new(answer), choices)
         }
         3 => {
-            let expr = gen_derivable(2);
-            let answer = simplify(&derive(&expr));
-            let problem = Expr::Derivative(Box::new(expr));
-            let choices: Vec<Maths> = gen_polynomial_choices(&answer, 0, 3)
+            let expr = gen_derivable(&mut rng, 2);
+            let answer = simplify(&derive(&expr, 'x'));
+            let problem = Expr::Derivative(Box::new(expr), 'x');
+            let choices: Vec<Maths> = gen_polynomial_choices(&mut rng, &answer, 0, 3)
                 .into_iter()
                 .map(|c| -> Maths { Box::new(c) })
                 .collect();
 
             (Box::new(problem), Box::new(answer), choices)
         }
+        4 => {
+            let (problem, answer) = gen_exact_trig(&mut rng);
+            let choices: Vec<Maths> = gen_exact_trig_choices(&mut rng, &problem, 3)
+                .into_iter()
+                .map(|c| -> Maths { Box::new(c) })
+                .collect();
+
+            (Box::new(problem), Box::new(answer), choices)
+        }
+        5 => {
+            let (problem, answer) = gen_integral(&mut rng, 2);
+            let choices: Vec<Maths> = gen_integral_choices(&mut rng, &answer, 2, 3)
+                .into_iter()
+                .map(|c| -> Maths { Box::new(c) })
+                .collect();
+
+            (Box::new(problem), Box::new(answer), choices)
+        }
+        6 => {
+            let (equation, answer) = gen_backtrack(&mut rng, 1, true);
+
+            let choices: Vec<Maths> = gen_choices(&mut rng, answer, 3)
+                .into_iter()
+                .map(|x| Equation {
+                    lhs: Expr::Variable('x'),
+                    rhs: x.into(),
+                })
+                .map(|c| -> Maths { Box::new(c) })
+                .collect();
+
+            let answer = Equation {
+                lhs: Expr::Variable('x'),
+                rhs: answer.into(),
+            };
+
+            (Box::new(equation), Box::new(answer), choices)
+        }
+        7 => {
+            let (system, answer) = gen_system(&mut rng, 2);
+            let choices: Vec<Maths> = gen_system_choices(&mut rng, &answer, 3)
+                .iter()
+                .map(|c| -> Maths { Box::new(System::assignments(c)) })
+                .collect();
+
+            (
+                Box::new(system),
+                Box::new(System::assignments(&answer)),
+                choices,
+            )
+        }
         _ => return Err(format!("Invalid level {level}")),
     };
 
-    let answer_index = rand::thread_rng().gen_range(0..(choices.len() + 1));
+    let indices: Vec<usize> = (0..(choices.len() + 1)).collect();
+    let answer_index = *indices.choose(&mut rng).unwrap();
     choices.insert(answer_index, answer);
 
     let letters = ['a', 'b', 'c', 'd'];
@@ -88,12 +145,18 @@ fn generate_multiple_choice_problem(level: u64) -> Result<(Maths, Vec<Choice>, u
     Ok((problem, choices, answer_index))
 }
 
-async fn generate_problem(problems_dir: &str, level: u64) -> Result<Problem, Box<dyn Error>> {
+async fn generate_problem(
+    problems_dir: &str,
+    level: u64,
+    seed: Option<u64>,
+) -> Result<Problem, Box<dyn Error>> {
     let env_var = env::var("mathoid_server").ok();
     let mathoid_server: Option<&str> = env_var.as_deref();
     let problem_uuid = uuid::Uuid::new_v4();
 
-    let (problem, choices, answer_index) = generate_multiple_choice_problem(level)?;
+    // fresh seeds stay within u32 so they survive a round trip through JSON numbers
+    let seed = seed.unwrap_or_else(|| rand::thread_rng().gen::<u32>() as u64);
+    let (problem, choices, answer_index) = generate_multiple_choice_problem(level, seed)?;
     let file = format!("{problem_uuid}_problem.png");
     let path = format!("{}/{}", problems_dir, &file);
     render_to_file(problem.as_ref(), &Path::new(&path), mathoid_server, false).await?;
@@ -110,6 +173,8 @@ async fn generate_problem(problems_dir: &str, level: u64) -> Result<Problem, Box
         problem_url: format!("/problem/{file}"),
         answer: answer_index,
         choice_urls,
+        level,
+        seed,
     })
 }
 
@@ -119,11 +184,17 @@ struct Problem {
     problem_url: String,
     answer: usize,
     choice_urls: Vec<String>,
+    level: u64,
+    seed: u64,
 }
 
-#[get("/rand-problem?<level>")]
-async fn rand_problem(config: &State<Config>, level: Option<u64>) -> Result<Json<Problem>, String> {
-    generate_problem(&config.problems_dir, level.unwrap_or(1))
+#[get("/rand-problem?<level>&<seed>")]
+async fn rand_problem(
+    config: &State<Config>,
+    level: Option<u64>,
+    seed: Option<u64>,
+) -> Result<Json<Problem>, String> {
+    generate_problem(&config.problems_dir, level.unwrap_or(1), seed)
         .await
         .map(|prob| Json(prob))
         .map_err(|err| format!("{:?}", err))
@@ -155,3 +226,46 @@ fn rocket() -> _ {
         .mount("/", routes![rand_problem, problem])
         .manage(config)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn latex(level: u64, seed: u64) -> (String, Vec<String>, usize) {
+        let (problem, choices, answer_index) =
+            generate_multiple_choice_problem(level, seed).expect("Level should exist");
+        let choices = choices
+            .iter()
+            .map(|choice| choice.answer.to_latex())
+            .collect();
+        (problem.to_latex(), choices, answer_index)
+    }
+
+    #[test]
+    fn same_seed_and_level_give_same_problem() {
+        for level in 1..=7 {
+            for seed in 0..10 {
+                assert_eq!(latex(level, seed), latex(level, seed));
+            }
+        }
+    }
+
+    #[test]
+    fn arithmetic_problems_do_not_panic() {
+        for seed in 0..500 {
+            latex(1, seed);
+        }
+    }
+
+    #[test]
+    fn derivative_problems_do_not_panic() {
+        for seed in 0..200 {
+            latex(3, seed);
+        }
+    }
+
+    #[test]
+    fn unknown_level_is_an_error() {
+        assert!(generate_multiple_choice_problem(0, 0).is_err());
+    }
+}
@@ -1,268 +1,432 @@
-use crate::{derive::derive, eval::eval, Expr, Op, Pair, Rational};
+use std::sync::OnceLock;
 
-pub fn simplify(expr: &Expr) -> Expr {
-    if let Ok(answer) = eval(expr) {
-        return answer.into();
+use crate::{
+    derive::derive,
+    eval::{eval_exact, EvalErr},
+    rewrite::{rewrite, Bindings, Pattern, Rule},
+    Constant, ExactVal, Expr, Func, Op, Pair, Rational,
+};
+
+fn is_zero(expr: &Expr) -> bool {
+    matches!(eval_exact(expr), Ok(ExactVal::Rational(rational)) if rational.numerator == 0)
+}
+
+// whether `expr` divides by zero anywhere, or is otherwise known not to have a value
+fn is_undefined(expr: &Expr) -> bool {
+    if matches!(
+        eval_exact(expr),
+        Err(EvalErr::DivisionByZero | EvalErr::NonReal)
+    ) {
+        return true;
     }
 
     match expr {
-        Expr::Rational(_) => expr.to_owned(),
         Expr::Pair(pair) => {
-            let lsimplified = simplify(&pair.left);
-            let rsimplified = simplify(&pair.right);
+            (pair.op == Op::Div && is_zero(&pair.right))
+                || is_undefined(&pair.left)
+                || is_undefined(&pair.right)
+        }
+        Expr::Negative(inner) | Expr::Func(_, inner) | Expr::Derivative(inner, _) => {
+            is_undefined(inner)
+        }
+        Expr::Rational(_) | Expr::Variable(_) | Expr::Constant(_) => false,
+    }
+}
 
-            if let Ok(answer) =
-                eval(&Pair::new(lsimplified.clone(), pair.op, rsimplified.clone()).into())
-            {
-                return answer.into();
-            }
+// rules which drop `e` altogether would otherwise hide that it has no value
+fn is_defined(bindings: &Bindings) -> bool {
+    bindings.get("e").is_some_and(|e| !is_undefined(e))
+}
+
+fn any(name: &'static str) -> Pattern {
+    Pattern::Any(name)
+}
+
+fn coefficient(name: &'static str) -> Pattern {
+    Pattern::Rational(name)
+}
 
-            match pair.op {
-                Op::Mul => match (lsimplified.clone(), rsimplified.clone()) {
-                    (Expr::Rational(rational), _) | (_, Expr::Rational(rational))
-                        if rational.numerator == 0 =>
-                    {
-                        return Rational::int(0).into()
-                    }
-                    (
-                        Expr::Rational(rational),
-                        Expr::Pair(box Pair {
-                            left: Expr::Rational(coefficient),
-                            op: Op::Mul,
-                            right: unknown,
-                        }),
-                    ) => {
-                        let new_coefficient = coefficient * rational;
-                        return Expr::Pair(Box::new(Pair::new(
-                            new_coefficient.into(),
-                            Op::Mul,
-                            unknown,
-                        )));
-                    }
-                    (Expr::Variable(_), Expr::Rational(_)) => {
-                        return Expr::Pair(Box::new(Pair::new(rsimplified, Op::Mul, lsimplified)));
-                    }
-                    (Expr::Negative(linner), Expr::Negative(rinner)) => {
-                        return Expr::Pair(Box::new(Pair::new(*linner, Op::Mul, *rinner)));
-                    }
-                    (Expr::Negative(box left), right) | (left, Expr::Negative(box right)) => {
-                        return Expr::Negative(Box::new(Pair::new(left, Op::Mul, right).into()));
-                    }
-                    (Expr::Rational(rational), right) if rational.numerator < 0 => {
-                        return Expr::Negative(Box::new(
-                            Pair::new((-rational).into(), Op::Mul, right).into(),
-                        ));
-                    }
-                    (Expr::Rational(rational), expr) | (expr, Expr::Rational(rational))
-                        if rational == Rational::int(1) =>
-                    {
-                        return expr;
-                    }
-                    (left, right) if left == right => {
-                        return Pair::new(left, Op::Pow, Rational::int(2).into()).into();
-                    }
-                    (
-                        left,
-                        Expr::Pair(box Pair {
-                            left: Expr::Rational(rational),
-                            op: Op::Mul,
-                            right,
-                        }),
-                    ) => {
-                        return Pair::new(
-                            rational.into(),
-                            Op::Mul,
-                            simplify(&Pair::new(left, Op::Mul, right).into()),
-                        )
-                        .into();
-                    }
-                    (
-                        left,
-                        Expr::Pair(box Pair {
-                            left: right,
-                            op: Op::Pow,
-                            right: exponent,
-                        }),
-                    )
-                    | (
-                        Expr::Pair(box Pair {
-                            left,
-                            op: Op::Pow,
-                            right: exponent,
-                        }),
-                        right,
-                    ) if left == right => {
-                        return Pair::new(
-                            left,
-                            Op::Pow,
-                            simplify(&Pair::new(exponent, Op::Add, Rational::int(1).into()).into()),
-                        )
-                        .into();
-                    }
-                    _ => (),
-                },
-                Op::Add => match (lsimplified.clone(), rsimplified.clone()) {
-                    (Expr::Rational(rational), right) if rational.numerator == 0 => return right,
-                    (left, Expr::Rational(rational)) if rational.numerator == 0 => return left,
-                    (left, Expr::Rational(rational)) if rational.numerator < 0 => {
-                        return Pair::new(left, Op::Sub, (-rational).into()).into()
-                    }
-                    (left, Expr::Negative(right)) => {
-                        return Expr::Pair(Box::new(Pair::new(left, Op::Sub, *right)))
-                    }
-                    (
-                        Expr::Pair(box Pair {
-                            left: Expr::Rational(lcoefficient),
-                            op: Op::Mul,
-                            right: lexpr,
-                        }),
-                        Expr::Pair(box Pair {
-                            left: Expr::Rational(rcoefficient),
-                            op: Op::Mul,
-                            right: rexpr,
-                        }),
-                    ) if lexpr == rexpr => {
-                        let new_coefficient = lcoefficient + rcoefficient;
-                        return Expr::Pair(Box::new(Pair::new(
-                            new_coefficient.into(),
-                            Op::Mul,
-                            lexpr,
-                        )));
-                    }
-                    _ => (),
-                },
-                Op::Sub => match (lsimplified.clone(), rsimplified.clone()) {
-                    (Expr::Rational(rational), right) if rational.numerator == 0 => {
-                        return Expr::Negative(Box::new(right))
-                    }
-                    (left, Expr::Rational(rational)) if rational.numerator == 0 => return left,
-                    (
-                        Expr::Pair(box Pair {
-                            left: Expr::Rational(lcoefficient),
-                            op: Op::Mul,
-                            right: lexpr,
-                        }),
-                        Expr::Pair(box Pair {
-                            left: Expr::Rational(rcoefficient),
-                            op: Op::Mul,
-                            right: rexpr,
-                        }),
-                    ) if lexpr == rexpr => {
-                        let new_coefficient = lcoefficient - rcoefficient;
-                        return Pair::new(
-                            new_coefficient.into(),
-                            Op::Mul,
-                            lexpr,
-                        ).into();
-                    }
-                    (
-                        lexpr,
-                        Expr::Pair(box Pair {
-                            left: Expr::Rational(rcoefficient),
-                            op: Op::Mul,
-                            right: rexpr,
-                        }),
-                    ) if lexpr == rexpr => {
-                        let new_coefficient = Rational::int(1) - rcoefficient;
-                        return Pair::new(
-                            new_coefficient.into(),
-                            Op::Mul,
-                            lexpr,
-                        ).into();
-                    }
-                    (
-                        Expr::Pair(box Pair {
-                            left: Expr::Rational(lcoefficient),
-                            op: Op::Mul,
-                            right: lexpr,
-                        }),
-                        rexpr,
-                    ) if lexpr == rexpr => {
-                        let new_coefficient = lcoefficient - Rational::int(1);
-                        return Pair::new(
-                            new_coefficient.into(),
-                            Op::Mul,
-                            lexpr,
-                        ).into();
-                    }
-                    _ => (),
-                },
-                Op::Pow if rsimplified == Rational::int(0).into() => {
-                    return Rational::int(1).into();
+fn int(n: i64) -> Pattern {
+    Pattern::exact(Rational::int(n))
+}
+
+fn pair(left: Pattern, op: Op, right: Pattern) -> Pattern {
+    Pattern::pair(left, op, right)
+}
+
+// `coefficient * name`, given the coefficient
+fn multiple(coefficient: Rational, bindings: &Bindings, name: &str) -> Option<Expr> {
+    Some(Pair::new(coefficient.into(), Op::Mul, bindings.get(name)?.to_owned()).into())
+}
+
+/// The rules `simplify` applies, in the order they're tried.
+pub fn simplification_rules() -> Vec<Rule> {
+    vec![
+        Rule::computed("evaluate", any("e"), |bindings| {
+            let expr = bindings.get("e")?;
+            let value: Expr = eval_exact(expr).ok()?.into();
+            (value != *expr).then_some(value)
+        }),
+        Rule::computed("differentiate", any("e"), |bindings| {
+            match bindings.get("e")? {
+                Expr::Derivative(inner, var) => Some(derive(inner, *var)),
+                _ => None,
+            }
+        }),
+        // products
+        Rule::new("multiply by zero", pair(int(0), Op::Mul, any("e")), int(0)).when(is_defined),
+        Rule::new("multiply by zero", pair(any("e"), Op::Mul, int(0)), int(0)).when(is_defined),
+        Rule::computed(
+            "multiply coefficients",
+            pair(
+                coefficient("a"),
+                Op::Mul,
+                pair(coefficient("b"), Op::Mul, any("e")),
+            ),
+            |bindings| {
+                let a = bindings.rational("a")?;
+                multiple(a.checked_mul(bindings.rational("b")?).ok()?, bindings, "e")
+            },
+        ),
+        Rule::new(
+            "coefficient first",
+            pair(Pattern::Variable("x"), Op::Mul, coefficient("a")),
+            pair(coefficient("a"), Op::Mul, Pattern::Variable("x")),
+        ),
+        Rule::new(
+            "negatives cancel",
+            pair(
+                Pattern::negative(any("a")),
+                Op::Mul,
+                Pattern::negative(any("b")),
+            ),
+            pair(any("a"), Op::Mul, any("b")),
+        ),
+        Rule::new(
+            "negative product",
+            pair(Pattern::negative(any("a")), Op::Mul, any("b")),
+            Pattern::negative(pair(any("a"), Op::Mul, any("b"))),
+        ),
+        Rule::new(
+            "negative product",
+            pair(any("a"), Op::Mul, Pattern::negative(any("b"))),
+            Pattern::negative(pair(any("a"), Op::Mul, any("b"))),
+        ),
+        Rule::computed(
+            "negative coefficient",
+            pair(coefficient("a"), Op::Mul, any("e")),
+            |bindings| {
+                let positive = bindings.rational("a")?.checked_neg().ok()?;
+                Some(Expr::Negative(Box::new(multiple(positive, bindings, "e")?)))
+            },
+        )
+        .when(|bindings| bindings.rational("a").is_some_and(|a| a.numerator < 0)),
+        Rule::new("multiply by one", pair(int(1), Op::Mul, any("e")), any("e")),
+        Rule::new("multiply by one", pair(any("e"), Op::Mul, int(1)), any("e")),
+        Rule::new(
+            "square",
+            pair(any("e"), Op::Mul, any("e")),
+            pair(any("e"), Op::Pow, int(2)),
+        ),
+        // two coefficients are left alone when multiplying them overflows, as swapping them would
+        // never settle
+        Rule::new(
+            "coefficient first",
+            pair(any("a"), Op::Mul, pair(coefficient("b"), Op::Mul, any("c"))),
+            pair(coefficient("b"), Op::Mul, pair(any("a"), Op::Mul, any("c"))),
+        )
+        .when(|bindings| bindings.rational("a").is_none()),
+        Rule::new(
+            "add exponents",
+            pair(any("e"), Op::Mul, pair(any("e"), Op::Pow, any("n"))),
+            pair(any("e"), Op::Pow, pair(any("n"), Op::Add, int(1))),
+        ),
+        Rule::new(
+            "add exponents",
+            pair(pair(any("e"), Op::Pow, any("n")), Op::Mul, any("e")),
+            pair(any("e"), Op::Pow, pair(any("n"), Op::Add, int(1))),
+        ),
+        // sums
+        Rule::new("add zero", pair(int(0), Op::Add, any("e")), any("e")),
+        Rule::new("add zero", pair(any("e"), Op::Add, int(0)), any("e")),
+        Rule::computed(
+            "subtract instead",
+            pair(any("e"), Op::Add, coefficient("a")),
+            |bindings| {
+                let positive = bindings.rational("a")?.checked_neg().ok()?;
+                Some(Pair::new(bindings.get("e")?.to_owned(), Op::Sub, positive.into()).into())
+            },
+        )
+        .when(|bindings| bindings.rational("a").is_some_and(|a| a.numerator < 0)),
+        Rule::new(
+            "subtract instead",
+            pair(any("a"), Op::Add, Pattern::negative(any("b"))),
+            pair(any("a"), Op::Sub, any("b")),
+        ),
+        Rule::computed(
+            "collect like terms",
+            pair(
+                pair(coefficient("a"), Op::Mul, any("e")),
+                Op::Add,
+                pair(coefficient("b"), Op::Mul, any("e")),
+            ),
+            |bindings| {
+                let a = bindings.rational("a")?;
+                multiple(a.checked_add(bindings.rational("b")?).ok()?, bindings, "e")
+            },
+        ),
+        // differences
+        Rule::new(
+            "subtract from zero",
+            pair(int(0), Op::Sub, any("e")),
+            Pattern::negative(any("e")),
+        ),
+        Rule::new("subtract zero", pair(any("e"), Op::Sub, int(0)), any("e")),
+        Rule::new("subtract itself", pair(any("e"), Op::Sub, any("e")), int(0)).when(is_defined),
+        Rule::computed(
+            "collect like terms",
+            pair(
+                pair(coefficient("a"), Op::Mul, any("e")),
+                Op::Sub,
+                pair(coefficient("b"), Op::Mul, any("e")),
+            ),
+            |bindings| {
+                let a = bindings.rational("a")?;
+                multiple(a.checked_sub(bindings.rational("b")?).ok()?, bindings, "e")
+            },
+        ),
+        Rule::computed(
+            "collect like terms",
+            pair(any("e"), Op::Sub, pair(coefficient("b"), Op::Mul, any("e"))),
+            |bindings| {
+                let b = bindings.rational("b")?;
+                multiple(Rational::int(1).checked_sub(b).ok()?, bindings, "e")
+            },
+        ),
+        Rule::computed(
+            "collect like terms",
+            pair(pair(coefficient("a"), Op::Mul, any("e")), Op::Sub, any("e")),
+            |bindings| {
+                let a = bindings.rational("a")?;
+                multiple(a.checked_sub(Rational::int(1)).ok()?, bindings, "e")
+            },
+        ),
+        // powers
+        Rule::new("zero power", pair(any("e"), Op::Pow, int(0)), int(1)).when(is_defined),
+        Rule::new("first power", pair(any("e"), Op::Pow, int(1)), any("e")),
+        Rule::new(
+            "square of a root",
+            pair(Pattern::func(Func::Sqrt, any("e")), Op::Pow, int(2)),
+            any("e"),
+        ),
+        Rule::computed(
+            "power of a logarithm",
+            pair(coefficient("b"), Op::Pow, any("log")),
+            |bindings| match bindings.get("log")? {
+                Expr::Func(Func::Log(base), inner)
+                    if bindings.rational("b")? == Rational::int(*base as i64) =>
+                {
+                    Some(*inner.to_owned())
                 }
-                Op::Pow if rsimplified == Rational::int(1).into() => return lsimplified.clone(),
-                Op::Pow => match (lsimplified.clone(), rsimplified.clone()) {
-                    (
-                        Expr::Pair(box Pair {
-                            left: base,
-                            op: Op::Pow,
-                            right: inner_power,
-                        }),
-                        outer_power,
-                    ) => {
-                        return Pair::new(
-                            base,
-                            Op::Pow,
-                            simplify(&Pair::new(inner_power, Op::Mul, outer_power).into()),
-                        )
-                        .into();
-                    }
-                    _ => (),
-                },
-                Op::Div => match (lsimplified.clone(), rsimplified.clone()) {
-                    (
-                        Expr::Pair(box Pair {
-                            left: numerator,
-                            op: Op::Div,
-                            right: denominator,
-                        }),
-                        second_denominator,
-                    ) => {
-                        return Pair::new(
-                            numerator,
-                            Op::Div,
-                            Pair::new(denominator, Op::Mul, second_denominator).into(),
-                        )
-                        .into()
-                    }
-                    (
-                        numerator,
-                        Expr::Pair(box Pair {
-                            left: denominator,
-                            op: Op::Div,
-                            right: second_numerator,
-                        }),
-                    ) => {
-                        return Pair::new(
-                            Pair::new(numerator, Op::Mul, second_numerator).into(),
-                            Op::Div,
-                            denominator,
-                        )
-                        .into()
-                    }
-                    _ => (),
-                },
+                _ => None,
+            },
+        ),
+        Rule::new(
+            "power of a logarithm",
+            pair(
+                Pattern::exact(Expr::Constant(Constant::E)),
+                Op::Pow,
+                Pattern::func(Func::Ln, any("e")),
+            ),
+            any("e"),
+        ),
+        Rule::new(
+            "power of a power",
+            pair(pair(any("a"), Op::Pow, any("b")), Op::Pow, any("c")),
+            pair(any("a"), Op::Pow, pair(any("b"), Op::Mul, any("c"))),
+        ),
+        // quotients, which are left alone when dividing by zero so that they stay undefined
+        Rule::new(
+            "divide twice",
+            pair(pair(any("n"), Op::Div, any("d")), Op::Div, any("d2")),
+            pair(any("n"), Op::Div, pair(any("d"), Op::Mul, any("d2"))),
+        )
+        .when(|bindings| bindings.get("d2").is_some_and(|d2| !is_zero(d2))),
+        Rule::new(
+            "divide by a quotient",
+            pair(any("n"), Op::Div, pair(any("d"), Op::Div, any("n2"))),
+            pair(pair(any("n"), Op::Mul, any("n2")), Op::Div, any("d")),
+        )
+        .when(|bindings| {
+            let nonzero = |name| bindings.get(name).is_some_and(|e| !is_zero(e));
+            nonzero("d") && nonzero("n2")
+        }),
+        // negatives and functions
+        Rule::new(
+            "double negative",
+            Pattern::negative(Pattern::negative(any("e"))),
+            any("e"),
+        ),
+        Rule::new(
+            "inverse functions",
+            Pattern::func(Func::Ln, Pattern::func(Func::Exp, any("e"))),
+            any("e"),
+        ),
+        Rule::new(
+            "inverse functions",
+            Pattern::func(Func::Exp, Pattern::func(Func::Ln, any("e"))),
+            any("e"),
+        ),
+        Rule::new(
+            "inverse functions",
+            Pattern::func(
+                Func::Ln,
+                pair(
+                    Pattern::exact(Expr::Constant(Constant::E)),
+                    Op::Pow,
+                    any("e"),
+                ),
+            ),
+            any("e"),
+        ),
+        Rule::computed("inverse functions", any("log"), |bindings| {
+            match bindings.get("log")? {
+                Expr::Func(
+                    Func::Log(log_base),
+                    box Expr::Pair(box Pair {
+                        left: Expr::Rational(base),
+                        op: Op::Pow,
+                        right: exponent,
+                    }),
+                ) if *base == Rational::int(*log_base as i64) => Some(exponent.to_owned()),
+                _ => None,
             }
+        }),
+        Rule::new(
+            "absolute value",
+            Pattern::func(Func::Abs, Pattern::func(Func::Abs, any("e"))),
+            Pattern::func(Func::Abs, any("e")),
+        ),
+        Rule::new(
+            "absolute value",
+            Pattern::func(Func::Abs, Pattern::negative(any("e"))),
+            Pattern::func(Func::Abs, any("e")),
+        ),
+        Rule::new(
+            "absolute value",
+            Pattern::func(Func::Sqrt, pair(any("e"), Op::Pow, int(2))),
+            Pattern::func(Func::Abs, any("e")),
+        ),
+    ]
+}
 
-            return Expr::Pair(Box::new(Pair::new(
-                lsimplified,
-                pair.op.clone(),
-                rsimplified,
-            )));
+pub fn simplify(expr: &Expr) -> Expr {
+    static RULES: OnceLock<Vec<Rule>> = OnceLock::new();
+    rewrite(expr, RULES.get_or_init(simplification_rules))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parse::parse_expr;
+
+    fn assert_simplifies(input: &str, expected: &str) {
+        assert_eq!(
+            simplify(&parse_expr(input).unwrap()),
+            parse_expr(expected).unwrap(),
+            "{}",
+            input
+        );
+    }
+
+    #[test]
+    fn inverse_functions_cancel() {
+        assert_simplifies("ln(e^x)", "x");
+        assert_simplifies("ln(exp(x))", "x");
+        assert_simplifies("exp(ln(x))", "x");
+        assert_simplifies("e^ln(x)", "x");
+        assert_simplifies("log(10^x)", "x");
+        assert_simplifies("sqrt(x)^2", "x");
+    }
+
+    #[test]
+    fn absolute_values_are_simplified() {
+        assert_simplifies("sqrt(x^2)", "abs(x)");
+        assert_simplifies("abs(-x)", "abs(x)");
+        assert_simplifies("abs(abs(x))", "abs(x)");
+    }
+
+    #[test]
+    fn functions_of_constants_are_simplified() {
+        assert_simplifies("sin(pi)", "0");
+        assert_simplifies("cos(pi)", "-1");
+        assert_simplifies("cos(2*pi)", "1");
+        assert_simplifies("ln(e)", "1");
+        assert_simplifies("pi + pi", "2*pi");
+    }
+
+    #[test]
+    fn collects_and_cancels_terms() {
+        assert_simplifies("x - x", "0");
+        assert_simplifies("(x + 1) - (1 + x)", "0");
+        assert_simplifies("sin(x) - sin(x)", "0");
+        assert_simplifies("3*x + 4*x", "7*x");
+        assert_simplifies("5*x - 2*x", "3*x");
+        assert_simplifies("x*3 + 0", "3*x");
+        assert_simplifies("2*(3*x)", "6*x");
+        assert_simplifies("x*x", "x^2");
+    }
+
+    #[test]
+    fn divisions_by_zero_are_kept() {
+        assert_simplifies("x/0", "x/0");
+        assert_simplifies("(2x + 3)/0", "(2*x + 3)/0");
+        assert_simplifies("1/(2 - 2)", "1/0");
+        assert_simplifies("0/0", "0/0");
+        assert_simplifies("x/(x - x)", "x/0");
+        assert_simplifies("3/0 + x", "3/0 + x");
+    }
+
+    #[test]
+    fn undefined_differences_are_not_cancelled() {
+        for input in ["x/0 - x/0", "1/(x - x) - 1/(x - x)", "sqrt(-1) - sqrt(-1)"] {
+            let simplified = simplify(&parse_expr(input).unwrap());
+            assert_ne!(simplified, Rational::int(0).into(), "{}", input);
         }
-        Expr::Negative(inner) => {
-            let simplified = simplify(inner);
-            match simplified {
-                Expr::Negative(expr) => *expr,
-                Expr::Pair(_)
-                | Expr::Rational(_)
-                | Expr::Variable(_)
-                | Expr::Derivative(_)
-                | Expr::Func(_, _) => Expr::Negative(Box::new(simplified)),
-            }
+        assert_simplifies("1/(x - x)", "1/0");
+    }
+
+    #[test]
+    fn undefined_factors_and_powers_are_kept() {
+        for input in [
+            "0*(x/0)",
+            "(x/0)*0",
+            "0*(1/(x - x))",
+            "(x/0)^0",
+            "(1/(x - x))^0",
+        ] {
+            let simplified = simplify(&parse_expr(input).unwrap());
+            assert!(is_undefined(&simplified), "{} gave {}", input, simplified);
         }
-        Expr::Variable(_) => expr.to_owned(),
-        Expr::Derivative(inner) => simplify(&derive(inner)),
-        Expr::Func(func, inner) => Expr::Func(*func, Box::new(simplify(&inner))),
+        assert_simplifies("0*(x/0)", "0*(x/0)");
+        assert_simplifies("(x/0)^0", "(x/0)^0");
+        assert_simplifies("0*(x + 1)", "0");
+        assert_simplifies("(x + 1)^0", "1");
+    }
+
+    #[test]
+    fn coefficients_which_overflow_are_left_in_place() {
+        let max: Expr = Rational::int(i64::MAX).into();
+        let product: Expr = Pair::new(
+            max.clone(),
+            Op::Mul,
+            Pair::new(max, Op::Mul, Expr::Variable('x')).into(),
+        )
+        .into();
+        // without the condition, the two "coefficient first" rules would swap them back and forth
+        assert!(simplification_rules()
+            .iter()
+            .all(|rule| rule.apply(&product).is_none()));
+        assert_eq!(simplify(&product), product);
     }
 }
@@ -1,41 +1,339 @@
+use std::collections::HashMap;
+
 use crate::{
     derive::derive,
-    eval::{eval, EvalErr},
-    Equation, Expr, Op, Pair, Rational,
+    eval::{eval, eval_exact, eval_f64, EvalErr},
+    polynomial::{Polynomial, PolynomialErr},
+    Equation, ExactVal, Expr, Op, Pair, Rational, System,
 };
 
+const BISECTIONS: usize = 200;
+const ROOT_TOLERANCE: f64 = 1e-9;
+
 #[derive(Debug)]
 pub enum SolveErr {
     TooManyUnknowns,
     NoUnknowns,
+    NotLinear,
+    NotPolynomial,
+    NoSolution,
+    InfinitelyMany,
+    Unsupported(Expr),
     FailedToEval(EvalErr),
 }
 
 impl Expr {
     pub fn unknown_count(&self) -> u64 {
         match self {
-            Expr::Rational(_) => 0,
+            Expr::Rational(_) | Expr::Constant(_) => 0,
             Expr::Pair(pair) => pair.left.unknown_count() + pair.right.unknown_count(),
             Expr::Negative(inner) => inner.unknown_count(),
             Expr::Variable(_) => 1,
-            Expr::Derivative(inner) => inner.unknown_count(),
+            Expr::Derivative(inner, _) => inner.unknown_count(),
             Expr::Func(_, inner) => inner.unknown_count(),
         }
     }
+
+    pub fn unknown_count_of(&self, var: char) -> u64 {
+        match self {
+            Expr::Rational(_) | Expr::Constant(_) => 0,
+            Expr::Pair(pair) => pair.left.unknown_count_of(var) + pair.right.unknown_count_of(var),
+            Expr::Negative(inner) => inner.unknown_count_of(var),
+            Expr::Variable(unknown) => (*unknown == var) as u64,
+            Expr::Derivative(inner, _) => inner.unknown_count_of(var),
+            Expr::Func(_, inner) => inner.unknown_count_of(var),
+        }
+    }
+}
+
+pub(crate) fn is_zero(rational: Rational) -> bool {
+    rational.numerator == 0
+}
+
+fn scale_terms(
+    (coefficients, constant): &(Vec<Rational>, Rational),
+    factor: Rational,
+) -> Result<(Vec<Rational>, Rational), EvalErr> {
+    let coefficients = coefficients
+        .iter()
+        .map(|c| c.checked_mul(factor))
+        .collect::<Result<_, _>>()?;
+    Ok((coefficients, constant.checked_mul(factor)?))
+}
+
+// `expr` as `a * x + b * y + ... + c`, when it's linear in each of `vars`
+fn linear_combination(expr: &Expr, vars: &[char]) -> Result<(Vec<Rational>, Rational), SolveErr> {
+    let zeros = vec![Rational::int(0); vars.len()];
+    if vars.iter().all(|var| expr.unknown_count_of(*var) == 0) {
+        let value = eval(expr).map_err(SolveErr::FailedToEval)?;
+        return Ok((zeros, value));
+    }
+
+    let is_constant =
+        |(coefficients, _): &(Vec<Rational>, Rational)| coefficients.iter().all(|c| is_zero(*c));
+
+    let terms = match expr {
+        Expr::Variable(var) => {
+            let mut coefficients = zeros;
+            let index = vars.iter().position(|v| v == var);
+            coefficients[index.expect("variables without a coefficient were evaluated")] =
+                Rational::int(1);
+            Ok((coefficients, Rational::int(0)))
+        }
+        Expr::Negative(inner) => scale_terms(&linear_combination(inner, vars)?, Rational::int(-1)),
+        Expr::Pair(pair) => {
+            let left = linear_combination(&pair.left, vars)?;
+            let right = linear_combination(&pair.right, vars)?;
+            match pair.op {
+                Op::Add | Op::Sub => {
+                    let sign = Rational::int(if pair.op == Op::Add { 1 } else { -1 });
+                    scale_terms(&right, sign).and_then(|(coefficients, constant)| {
+                        let coefficients = left
+                            .0
+                            .iter()
+                            .zip(coefficients)
+                            .map(|(l, r)| l.checked_add(r))
+                            .collect::<Result<_, _>>()?;
+                        Ok((coefficients, left.1.checked_add(constant)?))
+                    })
+                }
+                Op::Mul if is_constant(&left) => scale_terms(&right, left.1),
+                Op::Mul if is_constant(&right) => scale_terms(&left, right.1),
+                Op::Div if is_constant(&right) => Rational::int(1)
+                    .checked_div(right.1)
+                    .and_then(|reciprocal| scale_terms(&left, reciprocal)),
+                Op::Pow if is_constant(&right) && right.1 == Rational::int(1) => Ok(left),
+                _ => return Err(SolveErr::NotLinear),
+            }
+        }
+        Expr::Derivative(inner, wrt) => return linear_combination(&derive(inner, *wrt), vars),
+        Expr::Rational(_) | Expr::Constant(_) | Expr::Func(..) => return Err(SolveErr::NotLinear),
+    };
+
+    terms.map_err(SolveErr::FailedToEval)
+}
+
+// `expr` as `a * var + b`, when it's linear in `var`
+fn linear_terms(expr: &Expr, var: char) -> Result<(Rational, Rational), SolveErr> {
+    let (coefficients, constant) = linear_combination(expr, &[var])?;
+    Ok((coefficients[0], constant))
+}
+
+// `a * var + b`, leaving out any zero terms
+fn linear_expr(a: Rational, b: Rational, var: char) -> Expr {
+    let term = if a == Rational::int(1) {
+        Expr::Variable(var)
+    } else if a == Rational::int(-1) {
+        Expr::Negative(Box::new(Expr::Variable(var)))
+    } else {
+        Pair::new(a.into(), Op::Mul, Expr::Variable(var)).into()
+    };
+
+    match b.checked_neg() {
+        _ if is_zero(a) => b.into(),
+        _ if is_zero(b) => term,
+        Ok(magnitude) if b.numerator < 0 => Pair::new(term, Op::Sub, magnitude.into()).into(),
+        _ => Pair::new(term, Op::Add, b.into()).into(),
+    }
+}
+
+// collects like terms into `a x + b = c x + d`, then solves it directly
+fn solve_linear(equation: &Equation, var: char) -> Result<Solution, SolveErr> {
+    let (a, b) = linear_terms(&equation.lhs, var)?;
+    let (c, d) = linear_terms(&equation.rhs, var)?;
+
+    let mut steps = vec![];
+    let mut push = |reason, lhs, rhs| {
+        steps.push(SolveStep {
+            reason,
+            equation: Equation { lhs, rhs },
+        })
+    };
+    let collected = Equation {
+        lhs: linear_expr(a, b, var),
+        rhs: linear_expr(c, d, var),
+    };
+    if collected != *equation {
+        push(Reason::CollectTerms, collected.lhs, collected.rhs);
+    }
+
+    let a = a.checked_sub(c).map_err(SolveErr::FailedToEval)?;
+    if !is_zero(c) {
+        push(
+            Reason::Subtract(linear_expr(c, Rational::int(0), var)),
+            linear_expr(a, b, var),
+            d.into(),
+        );
+    }
+
+    if is_zero(a) {
+        return Err(if b == d {
+            SolveErr::InfinitelyMany
+        } else {
+            SolveErr::NoSolution
+        });
+    }
+
+    let d = d.checked_sub(b).map_err(SolveErr::FailedToEval)?;
+    if !is_zero(b) {
+        let reason = if b.numerator < 0 {
+            Reason::Add(b.checked_neg().map_err(SolveErr::FailedToEval)?.into())
+        } else {
+            Reason::Subtract(b.into())
+        };
+        push(reason, linear_expr(a, Rational::int(0), var), d.into());
+    }
+
+    let answer = d.checked_div(a).map_err(SolveErr::FailedToEval)?;
+    if a != Rational::int(1) {
+        push(Reason::Divide(a.into()), Expr::Variable(var), answer.into());
+    }
+
+    Ok(Solution {
+        equation: equation.clone(),
+        steps,
+        answer,
+    })
+}
+
+/// The unique solution of simultaneous linear equations, found exactly by Gaussian elimination.
+pub fn solve_system(system: &System) -> Result<HashMap<char, Rational>, SolveErr> {
+    let vars = system.variables();
+    if vars.is_empty() {
+        return Err(SolveErr::NoUnknowns);
+    }
+
+    // each row is `a x + b y + ... = c`, stored as `[a, b, ..., c]`
+    let mut rows = vec![];
+    for equation in &system.equations {
+        let (lcoefficients, lconstant) = linear_combination(&equation.lhs, &vars)?;
+        let (rcoefficients, rconstant) = linear_combination(&equation.rhs, &vars)?;
+        let mut row = lcoefficients
+            .into_iter()
+            .zip(rcoefficients)
+            .map(|(l, r)| l.checked_sub(r))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(SolveErr::FailedToEval)?;
+        row.push(
+            rconstant
+                .checked_sub(lconstant)
+                .map_err(SolveErr::FailedToEval)?,
+        );
+        rows.push(row);
+    }
+
+    let mut pivot_row = 0;
+    for column in 0..vars.len() {
+        let Some(pivot) = (pivot_row..rows.len()).find(|&row| !is_zero(rows[row][column])) else {
+            continue;
+        };
+        rows.swap(pivot_row, pivot);
+
+        let pivot = rows[pivot_row].clone();
+        for (i, row) in rows.iter_mut().enumerate() {
+            if i == pivot_row || is_zero(row[column]) {
+                continue;
+            }
+
+            let factor = row[column]
+                .checked_div(pivot[column])
+                .map_err(SolveErr::FailedToEval)?;
+            for (entry, pivot_entry) in row.iter_mut().zip(&pivot).skip(column) {
+                *entry = pivot_entry
+                    .checked_mul(factor)
+                    .and_then(|term| entry.checked_sub(term))
+                    .map_err(SolveErr::FailedToEval)?;
+            }
+        }
+        pivot_row += 1;
+    }
+
+    // a row of zero coefficients which still has a constant is `0 = c`
+    if rows[pivot_row..]
+        .iter()
+        .any(|row| !is_zero(row[vars.len()]))
+    {
+        return Err(SolveErr::NoSolution);
+    }
+    if pivot_row < vars.len() {
+        return Err(SolveErr::InfinitelyMany);
+    }
+
+    let mut solution = HashMap::new();
+    for (i, var) in vars.iter().enumerate() {
+        let value = rows[i][vars.len()]
+            .checked_div(rows[i][i])
+            .map_err(SolveErr::FailedToEval)?;
+        solution.insert(*var, value);
+    }
+    Ok(solution)
+}
+
+/// Why a step of a worked solution was taken, relative to the side with the unknown.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Reason {
+    SwapSides,
+    Negate,
+    Add(Expr),
+    Subtract(Expr),
+    Multiply(Expr),
+    Divide(Expr),
+    // k - u = c becomes u = k - c
+    SubtractFrom(Expr),
+    // k / u = c becomes u = k / c
+    DivideInto(Expr),
+    Root(Expr),
+    Differentiate(char),
+    CollectTerms,
+    Evaluate,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct SolveStep {
+    pub reason: Reason,
+    pub equation: Equation,
+}
+
+/// A worked solution, where each step's equation keeps the unknown on the left.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Solution {
+    pub equation: Equation,
+    pub steps: Vec<SolveStep>,
+    pub answer: Rational,
 }
 
 pub fn solve(equation: &Equation) -> Result<Rational, SolveErr> {
+    Ok(solve_with_steps(equation)?.answer)
+}
+
+// differentiating can remove the unknown, leaving an equation which either always or never holds
+fn without_unknown(lhs: &Expr, rhs: &Expr) -> SolveErr {
+    match (eval_exact(lhs), eval_exact(rhs)) {
+        (Ok(lval), Ok(rval)) if lval == rval => SolveErr::InfinitelyMany,
+        (Ok(_), Ok(_)) => SolveErr::NoSolution,
+        (Err(e), _) | (_, Err(e)) => SolveErr::FailedToEval(e),
+    }
+}
+
+pub fn solve_with_steps(equation: &Equation) -> Result<Solution, SolveErr> {
     let lunknowns = equation.lhs.unknown_count();
     let runknowns = equation.rhs.unknown_count();
 
     let unknowns = lunknowns + runknowns;
 
     if unknowns > 1 {
-        return Err(SolveErr::TooManyUnknowns);
+        let mut vars = equation.lhs.variables();
+        vars.extend(equation.rhs.variables());
+        vars.dedup();
+        return match vars[..] {
+            [var] => solve_linear(equation, var),
+            _ => Err(SolveErr::TooManyUnknowns),
+        };
     } else if unknowns == 0 {
         return Err(SolveErr::NoUnknowns);
     }
 
+    let mut steps = vec![];
     let mut side_with_unknown;
     let mut constant_side;
     if lunknowns == 1 {
@@ -44,23 +342,57 @@ pub fn solve(equation: &Equation) -> Result<Rational, SolveErr> {
     } else {
         side_with_unknown = equation.rhs.clone();
         constant_side = equation.lhs.clone();
+        steps.push(SolveStep {
+            reason: Reason::SwapSides,
+            equation: Equation {
+                lhs: side_with_unknown.clone(),
+                rhs: constant_side.clone(),
+            },
+        });
     }
 
     loop {
-        match side_with_unknown {
-            Expr::Rational(_) => panic!("Unknown disappeared"),
+        let reason = match side_with_unknown {
+            side if side.unknown_count() == 0 => {
+                return Err(without_unknown(&side, &constant_side))
+            }
+            side @ (Expr::Rational(_) | Expr::Constant(_)) => {
+                return Err(without_unknown(&side, &constant_side))
+            }
             Expr::Negative(expr) => {
                 side_with_unknown = *expr;
                 constant_side = Expr::Negative(Box::new(constant_side));
+                Reason::Negate
             }
-            Expr::Variable(_) => {
+            Expr::Variable(var) => {
                 let answer = eval(&constant_side).map_err(|e| SolveErr::FailedToEval(e))?;
-                return Ok(answer);
+                if constant_side != answer.into() {
+                    steps.push(SolveStep {
+                        reason: Reason::Evaluate,
+                        equation: Equation {
+                            lhs: Expr::Variable(var),
+                            rhs: answer.into(),
+                        },
+                    });
+                }
+
+                return Ok(Solution {
+                    equation: equation.clone(),
+                    steps,
+                    answer,
+                });
             }
             Expr::Pair(pair) => {
                 let lunknowns = pair.left.unknown_count();
                 if lunknowns == 1 {
                     side_with_unknown = pair.left;
+                    let reason = match pair.op {
+                        Op::Add => Reason::Subtract(pair.right.clone()),
+                        Op::Sub => Reason::Add(pair.right.clone()),
+                        Op::Mul => Reason::Divide(pair.right.clone()),
+                        Op::Div => Reason::Multiply(pair.right.clone()),
+                        Op::Pow => Reason::Root(pair.right.clone()),
+                    };
                     if pair.op == Op::Pow {
                         constant_side = Expr::Pair(Box::new(Pair::new(
                             constant_side,
@@ -78,28 +410,536 @@ pub fn solve(equation: &Equation) -> Result<Rational, SolveErr> {
                             pair.right,
                         )));
                     }
+                    reason
                 } else {
                     side_with_unknown = pair.right.clone();
                     match pair.op {
                         Op::Add | Op::Mul => {
+                            let reason = if pair.op == Op::Add {
+                                Reason::Subtract(pair.left.clone())
+                            } else {
+                                Reason::Divide(pair.left.clone())
+                            };
                             constant_side = Expr::Pair(Box::new(Pair::new(
                                 constant_side,
                                 pair.op.inverse(),
                                 pair.left,
-                            )))
+                            )));
+                            reason
                         }
                         Op::Div | Op::Sub => {
+                            let reason = if pair.op == Op::Sub {
+                                Reason::SubtractFrom(pair.left.clone())
+                            } else {
+                                Reason::DivideInto(pair.left.clone())
+                            };
                             constant_side =
-                                Expr::Pair(Box::new(Pair::new(pair.left, pair.op, constant_side)))
+                                Expr::Pair(Box::new(Pair::new(pair.left, pair.op, constant_side)));
+                            reason
                         }
-                        Op::Pow => unimplemented!("Logs not implemented yet"),
+                        // an unknown exponent would need logarithms
+                        Op::Pow => return Err(SolveErr::Unsupported(Expr::Pair(pair))),
                     }
                 }
             }
-            Expr::Derivative(inner) => {
-                side_with_unknown = derive(&*inner);
+            Expr::Derivative(inner, var) => {
+                side_with_unknown = derive(&*inner, var);
+                Reason::Differentiate(var)
             }
-            Expr::Func(_, _) => unimplemented!("Function backtracking isn't implemented"),
+            side @ Expr::Func(..) => return Err(SolveErr::Unsupported(side)),
+        };
+
+        steps.push(SolveStep {
+            reason,
+            equation: Equation {
+                lhs: side_with_unknown.clone(),
+                rhs: constant_side.clone(),
+            },
+        });
+    }
+}
+
+/// A real solution of a polynomial equation.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Root {
+    Exact(Expr),
+    // found numerically when the polynomial has no rational or quadratic factors left
+    Approximate(f64),
+}
+
+impl Root {
+    pub fn value(&self) -> f64 {
+        match self {
+            Root::Exact(expr) => eval_f64(expr, &HashMap::new()).unwrap_or(f64::NAN),
+            Root::Approximate(value) => *value,
         }
     }
 }
+
+fn quadratic_roots(coefficients: &[Rational]) -> Result<Vec<Root>, EvalErr> {
+    let (c, b, a) = (coefficients[0], coefficients[1], coefficients[2]);
+    let discriminant = b
+        .checked_mul(b)?
+        .checked_sub(Rational::int(4).checked_mul(a)?.checked_mul(c)?)?;
+    if discriminant.numerator < 0 {
+        return Ok(vec![]);
+    }
+
+    let two_a = Rational::int(2).checked_mul(a)?;
+    let centre = b.checked_neg()?.checked_div(two_a)?;
+    // the offset is kept positive so that the roots come out as `centre ± offset`
+    let scale = Rational::int(1).checked_div(two_a)?;
+    let scale = if scale.numerator < 0 {
+        scale.checked_neg()?
+    } else {
+        scale
+    };
+    let offset = ExactVal::Rational(scale).checked_mul(discriminant.root(2)?)?;
+
+    let roots = match offset {
+        ExactVal::Rational(offset) => vec![
+            Root::Exact(centre.checked_sub(offset)?.into()),
+            Root::Exact(centre.checked_add(offset)?.into()),
+        ],
+        offset if is_zero(centre) => vec![
+            Root::Exact(Expr::Negative(Box::new(offset.into()))),
+            Root::Exact(offset.into()),
+        ],
+        offset => vec![
+            Root::Exact(Pair::new(centre.into(), Op::Sub, offset.into()).into()),
+            Root::Exact(Pair::new(centre.into(), Op::Add, offset.into()).into()),
+        ],
+    };
+    Ok(roots)
+}
+
+// bisects between consecutive turning points, which are found the same way from the derivative
+fn approximate_roots(coefficients: &[f64]) -> Vec<f64> {
+    let degree = coefficients.len() - 1;
+    if degree == 1 {
+        return vec![-coefficients[0] / coefficients[1]];
+    }
+
+    let value = |x: f64| coefficients.iter().rev().fold(0.0, |acc, c| acc * x + c);
+    // every root is within the Cauchy bound
+    let leading = coefficients[degree];
+    let bound = 1.0
+        + coefficients[..degree]
+            .iter()
+            .map(|c| (c / leading).abs())
+            .fold(0.0, f64::max);
+
+    let derivative: Vec<f64> = coefficients
+        .iter()
+        .enumerate()
+        .skip(1)
+        .map(|(power, c)| c * power as f64)
+        .collect();
+    let mut points = vec![-bound];
+    points.extend(
+        approximate_roots(&derivative)
+            .into_iter()
+            .filter(|x| x.abs() < bound),
+    );
+    points.push(bound);
+
+    let mut roots = vec![];
+    for window in points.windows(2) {
+        let (mut lower, mut upper) = (window[0], window[1]);
+        // a turning point can touch zero without crossing it
+        if value(lower).abs() < ROOT_TOLERANCE {
+            roots.push(lower);
+            continue;
+        }
+        if value(upper).abs() < ROOT_TOLERANCE || value(lower).signum() == value(upper).signum() {
+            continue;
+        }
+
+        for _ in 0..BISECTIONS {
+            let middle = (lower + upper) / 2.0;
+            if value(middle).signum() == value(lower).signum() {
+                lower = middle;
+            } else {
+                upper = middle;
+            }
+        }
+        roots.push((lower + upper) / 2.0);
+    }
+    roots
+}
+
+/// Every real root of a polynomial equation in one unknown, in ascending order. Roots are exact
+/// when they're rational or come from a quadratic factor, and approximate otherwise.
+pub fn solve_polynomial(equation: &Equation) -> Result<Vec<Root>, SolveErr> {
+    let var = single_unknown(&equation.lhs, &equation.rhs)?;
+    let polynomial = difference(&equation.lhs, &equation.rhs, var)?;
+    match polynomial.degree() {
+        None => Err(SolveErr::InfinitelyMany),
+        Some(0) => Err(SolveErr::NoSolution),
+        _ => polynomial_roots(polynomial),
+    }
+}
+
+pub(crate) fn single_unknown(lhs: &Expr, rhs: &Expr) -> Result<char, SolveErr> {
+    let mut vars = lhs.variables();
+    vars.extend(rhs.variables());
+    vars.dedup();
+    match vars[..] {
+        [var] => Ok(var),
+        [] => Err(SolveErr::NoUnknowns),
+        _ => Err(SolveErr::TooManyUnknowns),
+    }
+}
+
+fn polynomial_err(err: PolynomialErr) -> SolveErr {
+    match err {
+        PolynomialErr::NotPolynomial(_) => SolveErr::NotPolynomial,
+        PolynomialErr::FailedToEval(e) => SolveErr::FailedToEval(e),
+    }
+}
+
+// `lhs - rhs` as a polynomial in `var`
+pub(crate) fn difference(lhs: &Expr, rhs: &Expr, var: char) -> Result<Polynomial, SolveErr> {
+    let lhs = Polynomial::from_expr(lhs, var).map_err(polynomial_err)?;
+    let rhs = Polynomial::from_expr(rhs, var).map_err(polynomial_err)?;
+    lhs.checked_sub(&rhs).map_err(SolveErr::FailedToEval)
+}
+
+// the real roots of a polynomial of at least degree 1, in ascending order
+pub(crate) fn polynomial_roots(mut polynomial: Polynomial) -> Result<Vec<Root>, SolveErr> {
+    let mut roots = vec![];
+    if is_zero(polynomial.coefficients[0]) {
+        roots.push(Root::Exact(Rational::int(0).into()));
+        let zero_roots = polynomial
+            .coefficients
+            .iter()
+            .take_while(|c| is_zero(**c))
+            .count();
+        polynomial.coefficients.drain(..zero_roots);
+    }
+
+    let candidates = polynomial
+        .rational_root_candidates()
+        .map_err(SolveErr::FailedToEval)?;
+    for candidate in candidates {
+        if polynomial.coefficients.len() <= 3 {
+            break;
+        }
+
+        // a root can be repeated, so it's divided out for as long as it divides evenly
+        let mut is_root = false;
+        loop {
+            let (quotient, remainder) = polynomial
+                .divide_by_root(candidate)
+                .map_err(SolveErr::FailedToEval)?;
+            if !is_zero(remainder) || polynomial.coefficients.len() == 1 {
+                break;
+            }
+            polynomial = quotient;
+            is_root = true;
+        }
+        if is_root {
+            roots.push(Root::Exact(candidate.into()));
+        }
+    }
+
+    let coefficients = polynomial.coefficients;
+    match coefficients.len() {
+        0 | 1 => (),
+        2 => {
+            let root = coefficients[0]
+                .checked_neg()
+                .and_then(|c| c.checked_div(coefficients[1]))
+                .map_err(SolveErr::FailedToEval)?;
+            roots.push(Root::Exact(root.into()));
+        }
+        3 => roots.extend(quadratic_roots(&coefficients).map_err(SolveErr::FailedToEval)?),
+        _ => {
+            let coefficients: Vec<f64> = coefficients
+                .iter()
+                .map(|c| c.numerator as f64 / c.denominator as f64)
+                .collect();
+            roots.extend(
+                approximate_roots(&coefficients)
+                    .into_iter()
+                    .map(Root::Approximate),
+            );
+        }
+    }
+
+    roots.sort_by(|a, b| a.value().total_cmp(&b.value()));
+    roots.dedup_by(|a, b| (a.value() - b.value()).abs() < ROOT_TOLERANCE);
+    Ok(roots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        eval::eval_with,
+        gen::{gen_backtrack, seeded_rng},
+        parse::parse_equation,
+        render::LatexConvertible,
+    };
+
+    fn equation(input: &str) -> Equation {
+        parse_equation(input).unwrap()
+    }
+
+    fn reasons(solution: &Solution) -> Vec<Reason> {
+        solution
+            .steps
+            .iter()
+            .map(|step| step.reason.clone())
+            .collect()
+    }
+
+    #[test]
+    fn records_each_step_with_its_reason() {
+        let solution = solve_with_steps(&equation("7 = 3 + 2*x")).unwrap();
+        assert_eq!(solution.answer, Rational::int(2));
+        assert_eq!(
+            reasons(&solution),
+            vec![
+                Reason::SwapSides,
+                Reason::Subtract(Rational::int(3).into()),
+                Reason::Divide(Rational::int(2).into()),
+                Reason::Evaluate,
+            ]
+        );
+        assert_eq!(solution.steps[2].equation, equation("x = (7 - 3)/2"));
+        assert_eq!(solution.steps.last().unwrap().equation, equation("x = 2"));
+    }
+
+    #[test]
+    fn undoes_operations_with_the_unknown_on_the_right() {
+        let solution = solve_with_steps(&equation("10 - x = 4")).unwrap();
+        assert_eq!(solution.answer, Rational::int(6));
+        assert_eq!(
+            solution.steps[0].reason,
+            Reason::SubtractFrom(Rational::int(10).into())
+        );
+
+        let solution = solve_with_steps(&equation("12/x = 4")).unwrap();
+        assert_eq!(solution.answer, Rational::int(3));
+        assert_eq!(
+            solution.steps[0].reason,
+            Reason::DivideInto(Rational::int(12).into())
+        );
+    }
+
+    #[test]
+    fn solutions_are_written_as_text_and_latex() {
+        let solution = solve_with_steps(&equation("2*x + 3 = 7")).unwrap();
+        let text = solution.to_string();
+        assert!(text.contains("(subtract 3 from both sides)"));
+        assert!(text.contains("(divide both sides by 2)"));
+
+        let latex = solution.to_latex();
+        assert!(latex.starts_with("\\begin{aligned} 2 x + 3 &= 7 \\\\ "));
+        assert!(latex.contains("\\text{subtract } 3 \\text{ from both sides}"));
+        assert!(latex.ends_with("x &= 2 && \\text{evaluate} \\end{aligned}"));
+    }
+
+    #[test]
+    fn unknowns_which_disappear_leave_no_or_every_solution() {
+        let derivative = |rhs: i64| Equation {
+            lhs: Expr::Derivative(Box::new(Expr::Variable('x')), 'x'),
+            rhs: Rational::int(rhs).into(),
+        };
+        assert!(matches!(
+            solve_with_steps(&derivative(1)),
+            Err(SolveErr::InfinitelyMany)
+        ));
+        assert!(matches!(
+            solve_with_steps(&derivative(2)),
+            Err(SolveErr::NoSolution)
+        ));
+    }
+
+    #[test]
+    fn unknowns_inside_functions_and_exponents_are_unsupported() {
+        assert!(matches!(
+            solve_with_steps(&equation("2^x = 8")),
+            Err(SolveErr::Unsupported(_))
+        ));
+        assert!(matches!(
+            solve_with_steps(&equation("sin(x) = 0")),
+            Err(SolveErr::Unsupported(_))
+        ));
+        assert!(matches!(
+            solve_with_steps(&equation("2 = 3")),
+            Err(SolveErr::NoUnknowns)
+        ));
+    }
+
+    #[test]
+    fn solves_with_the_unknown_on_both_sides() {
+        assert_eq!(
+            solve(&equation("2*x + 3 = x + 7")).ok(),
+            Some(Rational::int(4))
+        );
+        assert_eq!(
+            solve(&equation("3*(x - 1) = x + 2")).ok(),
+            Some(Rational::new(5, 2))
+        );
+        assert_eq!(
+            solve(&equation("x/2 + x/3 = 5")).ok(),
+            Some(Rational::int(6))
+        );
+
+        let solution = solve_with_steps(&equation("2*x + 3 = x + 7")).unwrap();
+        assert_eq!(
+            reasons(&solution),
+            vec![
+                Reason::Subtract(Expr::Variable('x')),
+                Reason::Subtract(Rational::int(3).into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn linear_equations_can_have_no_or_every_solution() {
+        assert!(matches!(
+            solve(&equation("2*x + 3 = 2*x + 4")),
+            Err(SolveErr::NoSolution)
+        ));
+        assert!(matches!(
+            solve(&equation("2*(x + 1) = 2*x + 2")),
+            Err(SolveErr::InfinitelyMany)
+        ));
+        assert!(matches!(
+            solve(&equation("x*x = x + 1")),
+            Err(SolveErr::NotLinear)
+        ));
+        assert!(matches!(
+            solve(&equation("x + y = y + 1")),
+            Err(SolveErr::TooManyUnknowns)
+        ));
+    }
+
+    #[test]
+    fn generated_equations_with_x_on_both_sides_have_their_answer() {
+        for seed in 0..50 {
+            let mut rng = seeded_rng(seed);
+            let (equation, answer) = gen_backtrack(&mut rng, 1, true);
+            assert!(equation.rhs.variables().contains(&'x'), "{}", equation);
+            assert_eq!(solve(&equation).ok(), Some(answer), "{}", equation);
+        }
+    }
+
+    fn exact_roots(input: &str) -> Vec<Expr> {
+        solve_polynomial(&equation(input))
+            .unwrap()
+            .into_iter()
+            .map(|root| match root {
+                Root::Exact(expr) => expr,
+                Root::Approximate(value) => panic!("{} has an approximate root {}", input, value),
+            })
+            .collect()
+    }
+
+    fn rationals(values: &[i64]) -> Vec<Expr> {
+        values
+            .iter()
+            .map(|&value| Rational::int(value).into())
+            .collect()
+    }
+
+    #[test]
+    fn polynomials_have_every_rational_root() {
+        assert_eq!(exact_roots("x^2 - 5*x + 6 = 0"), rationals(&[2, 3]));
+        assert_eq!(
+            exact_roots("x^3 - 6*x^2 + 11*x - 6 = 0"),
+            rationals(&[1, 2, 3])
+        );
+        assert_eq!(
+            exact_roots("x^4 - 5*x^2 + 4 = 0"),
+            rationals(&[-2, -1, 1, 2])
+        );
+        assert_eq!(exact_roots("x^3 = x"), rationals(&[-1, 0, 1]));
+        assert_eq!(exact_roots("(x - 1)^2 = 0"), rationals(&[1]));
+        assert_eq!(
+            exact_roots("2*x^2 + x - 1 = 0"),
+            vec![Rational::int(-1).into(), Rational::new(1, 2).into()]
+        );
+    }
+
+    #[test]
+    fn quadratic_roots_are_surds() {
+        let roots = solve_polynomial(&equation("x^2 + 2*x = 1")).unwrap();
+        let values: Vec<f64> = roots.iter().map(Root::value).collect();
+        assert!((values[0] - (-1.0 - 2f64.sqrt())).abs() < 1e-12);
+        assert!((values[1] - (-1.0 + 2f64.sqrt())).abs() < 1e-12);
+        assert!(roots.iter().all(|root| matches!(root, Root::Exact(_))));
+
+        assert_eq!(exact_roots("x^2 + 1 = 0"), vec![]);
+    }
+
+    #[test]
+    fn other_roots_are_approximate() {
+        let roots = solve_polynomial(&equation("x^5 - x - 1 = 0")).unwrap();
+        assert_eq!(roots.len(), 1);
+        assert!(matches!(roots[0], Root::Approximate(_)));
+        assert!((roots[0].value().powi(5) - roots[0].value() - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn polynomial_equations_can_have_no_or_every_solution() {
+        assert!(matches!(
+            solve_polynomial(&equation("x^2 + 1 = x^2")),
+            Err(SolveErr::NoSolution)
+        ));
+        assert!(matches!(
+            solve_polynomial(&equation("(x + 1)^2 = x^2 + 2*x + 1")),
+            Err(SolveErr::InfinitelyMany)
+        ));
+        assert!(matches!(
+            solve_polynomial(&equation("sin(x) = 0")),
+            Err(SolveErr::NotPolynomial)
+        ));
+    }
+
+    fn system(inputs: &[&str]) -> System {
+        System {
+            equations: inputs.iter().map(|input| equation(input)).collect(),
+        }
+    }
+
+    #[test]
+    fn solves_simultaneous_equations_exactly() {
+        let solution = solve_system(&system(&["x + y = 5", "x - y = 1"])).unwrap();
+        assert_eq!(
+            solution,
+            HashMap::from([('x', Rational::int(3)), ('y', Rational::int(2))])
+        );
+
+        let three = system(&["2*x + 3*y - z = 1", "x - y + 2*z = 2", "3*x + y = z + 1"]);
+        let solution = solve_system(&three).unwrap();
+        for equation in three.equations {
+            let lhs = eval_with(&equation.lhs, &solution).unwrap();
+            let rhs = eval_with(&equation.rhs, &solution).unwrap();
+            assert_eq!(lhs, rhs, "{}", equation);
+        }
+
+        let solution = solve_system(&system(&["2*x + y = 1", "x = 3*y"])).unwrap();
+        assert_eq!(solution[&'x'], Rational::new(3, 7));
+        assert_eq!(solution[&'y'], Rational::new(1, 7));
+    }
+
+    #[test]
+    fn dependent_systems_have_no_or_every_solution() {
+        assert!(matches!(
+            solve_system(&system(&["x + y = 1", "2*x + 2*y = 3"])),
+            Err(SolveErr::NoSolution)
+        ));
+        assert!(matches!(
+            solve_system(&system(&["x + y = 1", "2*x + 2*y = 2"])),
+            Err(SolveErr::InfinitelyMany)
+        ));
+        assert!(matches!(
+            solve_system(&system(&["x*y = 1", "x + y = 2"])),
+            Err(SolveErr::NotLinear)
+        ));
+    }
+}